@@ -0,0 +1,115 @@
+use crate::{
+    dwarf::{dump_die, type_index, Indices},
+    item::{Array, Language},
+};
+use anyhow::{anyhow, bail, Result};
+use gimli::{AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, RunTimeEndian, Unit};
+use log::debug;
+use std::str;
+
+pub fn from_array_type(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    module: &[String],
+    language: Language,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<Array> {
+    let mut name = None;
+    let mut ty = None;
+    let mut stride = None;
+
+    let mut attrs = die.attrs();
+    while let Some(attr) = attrs.next()? {
+        match attr.name() {
+            gimli::DW_AT_name => {
+                name = Some(str::from_utf8(&dwarf.attr_string(unit, attr.value())?)?.to_string());
+            }
+            gimli::DW_AT_type => {
+                ty = Some(type_index(dwarf, unit, indices, attr.value())?);
+            }
+            gimli::DW_AT_byte_stride => {
+                stride = attr.value().udata_value();
+            }
+            gimli::DW_AT_bit_stride => {
+                let bits = attr
+                    .value()
+                    .udata_value()
+                    .ok_or_else(|| anyhow!("Invalid DW_AT_bit_stride"))?;
+                if bits % 8 != 0 {
+                    bail!("Unsupported DW_AT_bit_stride: {}", bits);
+                }
+                stride = Some(bits / 8);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Array {
+        name,
+        module: module.to_vec(),
+        language,
+        type_index: ty.ok_or_else(|| anyhow!("Missing DW_AT_type"))?,
+        dimensions: Vec::new(),
+        stride,
+    })
+}
+
+pub fn modify(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    array: &mut Array,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<()> {
+    match die.tag() {
+        gimli::DW_TAG_subrange_type => {
+            let mut lower_bound = 0;
+            let mut upper_bound = None;
+            let mut count = None;
+
+            let mut attrs = die.attrs();
+            while let Some(attr) = attrs.next()? {
+                let value = || {
+                    attr.value().udata_value().ok_or_else(|| {
+                        anyhow!("Unsupported {} value: {:?}", attr.name(), attr.value())
+                    })
+                };
+                match attr.name() {
+                    gimli::DW_AT_lower_bound => {
+                        lower_bound = value()?;
+                    }
+                    gimli::DW_AT_upper_bound => match attr.value() {
+                        // GCC describes zero-length and flexible arrays with an upper bound of
+                        // -1, which it may also encode as an all-ones address-sized constant. An
+                        // all-ones Data1 or Data2 is a real bound, like the 255 of `int a[256]`.
+                        AttributeValue::Sdata(n) if n < 0 => count = Some(0),
+                        AttributeValue::Data4(u32::MAX) | AttributeValue::Data8(u64::MAX) => {
+                            count = Some(0)
+                        }
+                        _ => upper_bound = Some(value()?),
+                    },
+                    gimli::DW_AT_count => {
+                        count = Some(value()?);
+                    }
+                    _ => {}
+                }
+            }
+
+            // A subrange with no bounds is a flexible array member, which takes up no space.
+            let count = match (count, upper_bound) {
+                (Some(count), _) => count,
+                (None, Some(upper_bound)) => upper_bound
+                    .checked_add(1)
+                    .map_or(0, |end| end.saturating_sub(lower_bound)),
+                (None, None) => 0,
+            };
+            array.dimensions.push(count);
+        }
+        tag => {
+            debug!("In array: {:?}", array.name);
+            debug!("Unsupported tag: {}", tag);
+            dump_die(dwarf, unit, die, 0, "<ma> ")?
+        }
+    }
+    Ok(())
+}
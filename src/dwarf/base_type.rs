@@ -1,4 +1,4 @@
-use crate::item::{BaseType, BaseTypeKind};
+use crate::item::{BaseType, BaseTypeKind, Language};
 use anyhow::{anyhow, bail, Result};
 use gimli::{AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, RunTimeEndian, Unit};
 use std::str;
@@ -7,6 +7,7 @@ pub fn from_base_type(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
     module: &[String],
+    language: Language,
     die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
 ) -> Result<BaseType> {
     let mut name = None;
@@ -29,6 +30,8 @@ pub fn from_base_type(
                     AttributeValue::Encoding(gimli::DW_ATE_signed) => BaseTypeKind::SignedInt,
                     AttributeValue::Encoding(gimli::DW_ATE_unsigned) => BaseTypeKind::UnsignedInt,
                     AttributeValue::Encoding(gimli::DW_ATE_unsigned_char) => BaseTypeKind::Char,
+                    AttributeValue::Encoding(gimli::DW_ATE_signed_char) => BaseTypeKind::Char,
+                    AttributeValue::Encoding(gimli::DW_ATE_UTF) => BaseTypeKind::Char,
                     AttributeValue::Encoding(e) => bail!("Invalid DW_AT_encoding: {}", e),
                     val => bail!("Invalid DW_AT_encoding: {:?}", val),
                 });
@@ -40,6 +43,7 @@ pub fn from_base_type(
     let mut bt = BaseType {
         name: name.ok_or_else(|| anyhow!("Missing DW_AT_name"))?,
         module: module.to_vec(),
+        language,
         size: size.ok_or_else(|| anyhow!("Missing or invalid DW_AT_byte_size"))?,
         kind: kind.ok_or_else(|| anyhow!("Missing DW_AT_encoding"))?,
     };
@@ -0,0 +1,135 @@
+use crate::{
+    dwarf::{dump_die, member_offset, type_index, Indices},
+    item::{Enum, EnumDiscriminant, EnumVariant, Structure},
+};
+use anyhow::{anyhow, bail, Result};
+use gimli::{
+    AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, EntriesTreeNode, RunTimeEndian,
+    Unit,
+};
+use log::debug;
+use std::str;
+
+/// Converts a structure containing a `DW_TAG_variant_part` into an enum.
+pub fn from_variant_part(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    structure: &Structure,
+    node: EntriesTreeNode<EndianSlice<RunTimeEndian>>,
+) -> Result<Enum> {
+    let mut enum_ = Enum {
+        name: structure.name.clone(),
+        module: structure.module.clone(),
+        language: structure.language,
+        size: structure.size,
+        alignment: structure.alignment,
+        alignment_inferred: structure.alignment_inferred,
+        discriminant: None,
+        variants: Vec::new(),
+    };
+
+    // The variant part refers to the member holding the discriminant, which is usually one of
+    // its children.
+    let discr = match node.entry().attr_value(gimli::DW_AT_discr)? {
+        Some(AttributeValue::UnitRef(offset)) => Some(offset),
+        _ => None,
+    };
+
+    let mut iter = node.children();
+    while let Some(node) = iter.next()? {
+        let die = node.entry();
+        match die.tag() {
+            gimli::DW_TAG_member if discr.unwrap_or(die.offset()) == die.offset() => {
+                let (_, type_index, offset) = from_member(dwarf, unit, indices, die)?;
+                enum_.discriminant = Some(EnumDiscriminant { type_index, offset });
+            }
+            gimli::DW_TAG_variant => {
+                let mut discriminant = None;
+                let mut attrs = die.attrs();
+                while let Some(attr) = attrs.next()? {
+                    match attr.name() {
+                        gimli::DW_AT_discr_value => {
+                            discriminant = Some(match attr.value() {
+                                AttributeValue::Sdata(n) => n as u64,
+                                val => val
+                                    .udata_value()
+                                    .ok_or_else(|| anyhow!("Invalid DW_AT_discr_value"))?,
+                            });
+                        }
+                        gimli::DW_AT_discr_list => bail!("DW_AT_discr_list is not supported"),
+                        _ => {}
+                    }
+                }
+
+                let mut iter = node.children();
+                while let Some(node) = iter.next()? {
+                    let die = node.entry();
+                    if die.tag() != gimli::DW_TAG_member {
+                        debug!("In enum variant: {}", enum_.name);
+                        debug!("Unsupported tag: {}", die.tag());
+                        dump_die(dwarf, unit, die, 0, "<mv> ")?;
+                        continue;
+                    }
+
+                    let (name, type_index, offset) = from_member(dwarf, unit, indices, die)?;
+                    enum_.variants.push(EnumVariant {
+                        name: name.ok_or_else(|| anyhow!("Missing DW_AT_name"))?,
+                        discriminant,
+                        type_index,
+                        offset,
+                    });
+                }
+            }
+            tag => {
+                debug!("In enum: {}", enum_.name);
+                debug!("Unsupported tag: {}", tag);
+                dump_die(dwarf, unit, die, 0, "<me> ")?
+            }
+        }
+    }
+
+    if let (None, Some(discr)) = (&enum_.discriminant, discr) {
+        let mut entries = unit.entries_at_offset(discr)?;
+        if let Some((_, die)) = entries.next_dfs()? {
+            let (_, type_index, offset) = from_member(dwarf, unit, indices, die)?;
+            enum_.discriminant = Some(EnumDiscriminant { type_index, offset });
+        }
+    }
+
+    Ok(enum_)
+}
+
+/// Parses the name, type index, and offset of a `DW_TAG_member`.
+fn from_member(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<(Option<String>, usize, u64)> {
+    let mut name = None;
+    let mut ty = None;
+    let mut offset = None;
+
+    let mut attrs = die.attrs();
+    while let Some(attr) = attrs.next()? {
+        match attr.name() {
+            gimli::DW_AT_name => {
+                name = Some(str::from_utf8(&dwarf.attr_string(unit, attr.value())?)?.to_string());
+            }
+            gimli::DW_AT_type => {
+                ty = Some(type_index(dwarf, unit, indices, attr.value())?);
+            }
+            gimli::DW_AT_data_member_location => {
+                offset = Some(member_offset(unit, attr.value())?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok((
+        name,
+        ty.ok_or_else(|| anyhow!("Missing DW_AT_type"))?,
+        offset.ok_or_else(|| anyhow!("Missing DW_AT_data_member_location"))?,
+    ))
+}
@@ -1,16 +1,23 @@
-use crate::{dwarf::dump_die, item::Function};
-use anyhow::{anyhow, bail, Result};
-use gimli::{
-    AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, RunTimeEndian, Unit, UnitOffset,
+use crate::{
+    dwarf::{
+        dump_die, enclosing_module, full_name, is_library_name, template_parameter, type_index,
+        unit_containing, Indices,
+    },
+    item::{Function, Language, Symbol, SymbolBinding},
 };
+use anyhow::{anyhow, bail, Result};
+use gimli::{AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, RunTimeEndian, Unit};
 use log::debug;
-use rustc_demangle::demangle;
-use std::str;
+use object::{ElfFile, Object, SymbolKind};
+use rustc_demangle::try_demangle;
+use std::{collections::HashMap, str};
 
 pub fn from_subprogram(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
     module: &[String],
+    language: Language,
     die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
 ) -> Result<Option<Function>> {
     let string =
@@ -19,6 +26,8 @@ pub fn from_subprogram(
     let mut name = None;
     let mut linkage_name = None;
     let mut ret_type_index = None;
+    let mut external = None;
+    let mut specification = None;
 
     let mut attrs = die.attrs();
     while let Some(attr) = attrs.next()? {
@@ -26,17 +35,17 @@ pub fn from_subprogram(
             gimli::DW_AT_name => {
                 name = Some(string(attr.value())?);
             }
-            gimli::DW_AT_external => match attr.value() {
-                AttributeValue::Flag(true) => {}
-                _ => return Ok(None),
-            },
+            gimli::DW_AT_external => {
+                external = Some(attr.value() == AttributeValue::Flag(true));
+            }
+            gimli::DW_AT_declaration if language != Language::Rust => return Ok(None),
+            gimli::DW_AT_specification | gimli::DW_AT_abstract_origin => {
+                specification = Some(attr.value());
+            }
             gimli::DW_AT_type => {
-                ret_type_index = Some(match attr.value() {
-                    AttributeValue::UnitRef(UnitOffset(n)) => n,
-                    val => bail!("Unexpected DW_AT_type value: {:?}", val),
-                });
+                ret_type_index = Some(type_index(dwarf, unit, indices, attr.value())?);
             }
-            gimli::DW_AT_linkage_name => {
+            gimli::DW_AT_linkage_name | gimli::DW_AT_MIPS_linkage_name => {
                 let name = string(attr.value())?;
                 linkage_name = Some(name);
             }
@@ -44,29 +53,78 @@ pub fn from_subprogram(
         }
     }
 
-    let linkage_name = linkage_name.ok_or_else(|| {
-        let _ = dump_die(dwarf, unit, die, 0, "<ef> ");
-        anyhow!(
-            "Missing DW_AT_linkage_name from {:?} in {:?} at 0x{:x}",
-            name,
-            module,
-            die.offset().0
-        )
-    })?;
-    let full_name = demangle(&linkage_name).to_string();
-    // Likely nix some of these.
-    if full_name.starts_with("alloc::")
-        || full_name.starts_with("backtrace::")
-        || full_name.starts_with("compiler_builtins::")
-        || full_name.starts_with("core::")
-        || full_name.starts_with("libc::")
-        || full_name.starts_with("panic_unwind::")
-        || full_name.starts_with("rust_")
-        || full_name.starts_with("rustc_demangle::")
-        || full_name.starts_with("std::")
-        || full_name.starts_with("<")
-        || full_name.starts_with("__")
-    {
+    // C++ member functions are declared in their class, and defined out of line with a reference
+    // back to the declaration, which has the name and linkage name. Out of line definitions are at
+    // the top level, so their namespaces and class come from the declaration too.
+    let mut decl_module = None;
+    let other_unit;
+    let decl = match specification {
+        Some(AttributeValue::UnitRef(offset)) => Some((unit, offset)),
+        Some(AttributeValue::DebugInfoRef(offset)) => {
+            let (decl_unit, offset) = unit_containing(dwarf, offset)?;
+            other_unit = decl_unit;
+            Some((&other_unit, offset))
+        }
+        _ => None,
+    };
+    if let Some((decl_unit, offset)) = decl {
+        decl_module = Some(enclosing_module(dwarf, decl_unit, offset)?);
+        let decl_string = |val| -> Result<_> {
+            Ok(str::from_utf8(&dwarf.attr_string(decl_unit, val)?)?.to_string())
+        };
+        let mut entries = decl_unit.entries_at_offset(offset)?;
+        let decl = match entries.next_dfs()? {
+            Some((_, decl)) => decl,
+            None => bail!("Missing specification at 0x{:x}", offset.0),
+        };
+        let mut attrs = decl.attrs();
+        while let Some(attr) = attrs.next()? {
+            match attr.name() {
+                gimli::DW_AT_name if name.is_none() => {
+                    name = Some(decl_string(attr.value())?);
+                }
+                gimli::DW_AT_external if external.is_none() => {
+                    external = Some(attr.value() == AttributeValue::Flag(true));
+                }
+                gimli::DW_AT_type if ret_type_index.is_none() => {
+                    ret_type_index = Some(type_index(dwarf, decl_unit, indices, attr.value())?);
+                }
+                gimli::DW_AT_linkage_name | gimli::DW_AT_MIPS_linkage_name
+                    if linkage_name.is_none() =>
+                {
+                    linkage_name = Some(decl_string(attr.value())?);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let module = decl_module.as_deref().unwrap_or(module);
+
+    match (external, language) {
+        (Some(false), _) => return Ok(None),
+        // C and C++ mark every externally visible function, so the rest are static.
+        (None, Language::C) | (None, Language::Cpp) => return Ok(None),
+        _ => {}
+    }
+
+    // Unmangled functions have no linkage name, since it would be the same as their name. These
+    // are C functions, extern "C" functions in C++ and #[no_mangle] functions in Rust.
+    let linkage_name = match (linkage_name, &name) {
+        (Some(linkage_name), _) => linkage_name,
+        (None, Some(name)) => name.clone(),
+        (None, None) => {
+            let _ = dump_die(dwarf, unit, die, 0, "<ef> ");
+            bail!(
+                "Missing DW_AT_linkage_name from {:?} in {:?} at 0x{:x}",
+                name,
+                module,
+                die.offset().0
+            )
+        }
+    };
+    let full_name = full_name(language, module, name.as_ref(), &linkage_name);
+    if is_library_name(&full_name) {
         return Ok(None);
     }
 
@@ -75,14 +133,20 @@ pub fn from_subprogram(
         linkage_name,
         full_name,
         module: module.to_vec(),
+        language,
         ret_type_index,
         arguments: Vec::new(),
+        template_parameters: Vec::new(),
+        // Filled in by get_items.
+        symbol: None,
+        unknown_signature: false,
     }))
 }
 
 pub fn modify(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
     function: &mut Function,
     die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
 ) -> Result<()> {
@@ -101,10 +165,7 @@ pub fn modify(
                         name = Some(string(attr.value())?);
                     }
                     gimli::DW_AT_type => {
-                        ty = Some(match attr.value() {
-                            AttributeValue::UnitRef(UnitOffset(n)) => n,
-                            val => bail!("Unexpected DW_AT_type value: {:?}", val),
-                        });
+                        ty = Some(type_index(dwarf, unit, indices, attr.value())?);
                     }
                     _ => {}
                 }
@@ -114,6 +175,11 @@ pub fn modify(
                 ty.ok_or_else(|| anyhow!("Missing DW_AT_type from DW_TAG_formal_parameter"))?;
             function.arguments.push((name, ty));
         }
+        gimli::DW_TAG_template_type_parameter => {
+            let param =
+                template_parameter::from_template_type_parameter(dwarf, unit, indices, die)?;
+            function.template_parameters.push(param);
+        }
         tag => {
             debug!("In function: {}", function.full_name);
             debug!("Unsupported tag: {}", tag);
@@ -122,3 +188,76 @@ pub fn modify(
     }
     Ok(())
 }
+
+const STT_GNU_IFUNC: u8 = 10;
+
+/// Gets the functions exported from the dynamic symbol table of a file, by name. This includes
+/// `STT_GNU_IFUNC` symbols, whose implementation is picked at load time, which `object` doesn't
+/// count as functions.
+pub fn exported_symbols(elf: &ElfFile) -> HashMap<String, Symbol> {
+    let dynsyms = &elf.elf().dynsyms;
+    elf.dynamic_symbols()
+        .filter(|(index, symbol)| {
+            let is_function = symbol.kind() == SymbolKind::Text
+                || matches!(dynsyms.get(index.0), Some(sym) if sym.st_type() == STT_GNU_IFUNC);
+            is_function && !symbol.is_undefined() && !symbol.is_local()
+        })
+        .filter_map(|(_, symbol)| {
+            let binding = if symbol.is_weak() {
+                SymbolBinding::Weak
+            } else {
+                SymbolBinding::Global
+            };
+            Some((
+                symbol.name()?.to_string(),
+                Symbol {
+                    address: symbol.address(),
+                    size: symbol.size(),
+                    binding,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Makes a stub function for an exported symbol that isn't described by the debug info. Its name
+/// is demangled if it's a Rust symbol, but its signature is unknown. The ELF initialization
+/// functions and reserved `__` names, like those of the C runtime, are skipped.
+pub fn from_symbol(linkage_name: &str, symbol: &Symbol) -> Option<Function> {
+    if linkage_name == "_init" || linkage_name == "_fini" || linkage_name.starts_with("__") {
+        return None;
+    }
+
+    let (language, full_name) = match try_demangle(linkage_name) {
+        Ok(demangled) => (Language::Rust, format!("{:#}", demangled)),
+        Err(_) if linkage_name.starts_with("_Z") => (Language::Cpp, linkage_name.to_string()),
+        Err(_) => (Language::C, linkage_name.to_string()),
+    };
+    if is_library_name(&full_name) {
+        return None;
+    }
+
+    // Only Rust paths are split into the module and name, since C++ names are left mangled.
+    let mut module = if language == Language::Rust {
+        full_name
+            .split("::")
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    } else {
+        vec![full_name.clone()]
+    };
+    let name = module.pop();
+
+    Some(Function {
+        name,
+        linkage_name: linkage_name.to_string(),
+        full_name,
+        module,
+        language,
+        ret_type_index: None,
+        arguments: Vec::new(),
+        template_parameters: Vec::new(),
+        symbol: Some(symbol.clone()),
+        unknown_signature: true,
+    })
+}
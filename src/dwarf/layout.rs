@@ -0,0 +1,357 @@
+use crate::item::{Item, Language, QualifiedType, Repr, Structure, StructureMember, Typedef};
+use log::warn;
+use std::collections::HashMap;
+
+/// Fills in the alignments that weren't given by `DW_AT_alignment` with the natural alignment of
+/// each type, for a target with the given pointer size.
+pub fn infer_alignments(items: &mut [(usize, Item)], pointer_size: u64) {
+    let mut alignments = HashMap::new();
+    {
+        let by_index = items
+            .iter()
+            .map(|(index, item)| (*index, item))
+            .collect::<HashMap<_, _>>();
+        for (index, _) in items.iter() {
+            alignment(&by_index, pointer_size, &mut alignments, *index);
+        }
+    }
+
+    let member_alignment = |member: &StructureMember| {
+        if member.alignment != 0 {
+            member.alignment
+        } else {
+            alignments.get(&member.type_index).copied().unwrap_or(1)
+        }
+    };
+    for (index, item) in items.iter_mut() {
+        let alignment = alignments.get(index).copied().unwrap_or(1);
+        match item {
+            Item::Structure(structure) | Item::Closure(structure) | Item::ZeroSized(structure) => {
+                for member in &mut structure.members {
+                    member.alignment = member_alignment(member);
+                }
+                if structure.alignment_inferred {
+                    structure.alignment = alignment;
+                }
+            }
+            Item::Union(union) => {
+                for member in &mut union.members {
+                    member.alignment = member_alignment(member);
+                }
+                if union.alignment_inferred {
+                    union.alignment = alignment;
+                }
+            }
+            Item::Tuple(tuple) => {
+                for element in &mut tuple.elements {
+                    element.alignment = member_alignment(element);
+                }
+                if tuple.alignment_inferred {
+                    tuple.alignment = alignment;
+                }
+            }
+            Item::Slice(slice) | Item::Str(slice) => {
+                slice.data_ptr.alignment = member_alignment(&slice.data_ptr);
+                slice.length.alignment = member_alignment(&slice.length);
+                if slice.alignment_inferred {
+                    slice.alignment = alignment;
+                }
+            }
+            Item::TraitObject(object) => {
+                object.pointer.alignment = member_alignment(&object.pointer);
+                object.vtable.alignment = member_alignment(&object.vtable);
+                if object.alignment_inferred {
+                    object.alignment = alignment;
+                }
+            }
+            Item::Enum(enum_) if enum_.alignment_inferred => {
+                enum_.alignment = alignment;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Gets the alignment of the type with the given index, using the explicit alignment if there is
+/// one.
+fn alignment(
+    items: &HashMap<usize, &Item>,
+    pointer_size: u64,
+    alignments: &mut HashMap<usize, u64>,
+    index: usize,
+) -> u64 {
+    if let Some(&alignment) = alignments.get(&index) {
+        return alignment;
+    }
+    // Guards against cycles in malformed debug info.
+    alignments.insert(index, 1);
+
+    let alignment = match items.get(&index) {
+        // The i386 System V ABI aligns 8-byte scalars and `long double` to 4 bytes.
+        Some(Item::BaseType(ty)) => {
+            let max = if pointer_size < 8 { pointer_size } else { 16 };
+            ty.size.next_power_of_two().clamp(1, max)
+        }
+        Some(Item::PointerType(_)) => pointer_size,
+        Some(Item::Structure(ty)) | Some(Item::Closure(ty)) | Some(Item::ZeroSized(ty))
+            if ty.alignment_inferred =>
+        {
+            let members = ty.members.iter().collect::<Vec<_>>();
+            members_alignment(items, pointer_size, alignments, &members)
+        }
+        Some(Item::Union(ty)) if ty.alignment_inferred => {
+            let members = ty.members.iter().collect::<Vec<_>>();
+            members_alignment(items, pointer_size, alignments, &members)
+        }
+        Some(Item::Tuple(ty)) if ty.alignment_inferred => {
+            let elements = ty.elements.iter().collect::<Vec<_>>();
+            members_alignment(items, pointer_size, alignments, &elements)
+        }
+        Some(Item::Slice(ty)) | Some(Item::Str(ty)) if ty.alignment_inferred => {
+            members_alignment(items, pointer_size, alignments, &[&ty.data_ptr, &ty.length])
+        }
+        Some(Item::TraitObject(ty)) if ty.alignment_inferred => {
+            members_alignment(items, pointer_size, alignments, &[&ty.pointer, &ty.vtable])
+        }
+        Some(Item::Enum(ty)) if ty.alignment_inferred => {
+            let mut max = 1;
+            let indices = ty
+                .discriminant
+                .iter()
+                .map(|discriminant| discriminant.type_index)
+                .chain(ty.variants.iter().map(|variant| variant.type_index));
+            for index in indices {
+                max = max.max(alignment(items, pointer_size, alignments, index));
+            }
+            max
+        }
+        Some(Item::Structure(ty)) | Some(Item::Closure(ty)) | Some(Item::ZeroSized(ty)) => {
+            ty.alignment
+        }
+        Some(Item::Union(ty)) => ty.alignment,
+        Some(Item::Tuple(ty)) => ty.alignment,
+        Some(Item::Slice(ty)) | Some(Item::Str(ty)) => ty.alignment,
+        Some(Item::TraitObject(ty)) => ty.alignment,
+        Some(Item::Enum(ty)) => ty.alignment,
+        Some(Item::Array(ty)) => alignment(items, pointer_size, alignments, ty.type_index),
+        Some(Item::Typedef(Typedef {
+            type_index: Some(index),
+            ..
+        }))
+        | Some(Item::QualifiedType(QualifiedType {
+            type_index: Some(index),
+            ..
+        })) => alignment(items, pointer_size, alignments, *index),
+        _ => 1,
+    };
+    alignments.insert(index, alignment);
+    alignment
+}
+
+/// Gets the alignment of a structure with the given members, which is that of its most aligned
+/// member.
+fn members_alignment(
+    items: &HashMap<usize, &Item>,
+    pointer_size: u64,
+    alignments: &mut HashMap<usize, u64>,
+    members: &[&StructureMember],
+) -> u64 {
+    let mut max = 1;
+    for member in members {
+        let member_alignment = if member.alignment != 0 {
+            member.alignment
+        } else {
+            alignment(items, pointer_size, alignments, member.type_index)
+        };
+        max = max.max(member_alignment);
+    }
+    max
+}
+
+/// Labels each structure with the `repr` its layout is consistent with. Rust gives no guarantees
+/// about the layout of the default representation, so structures whose layout happens to match
+/// `repr(C)` are labelled as such.
+pub fn classify_structures(items: &mut [(usize, Item)], pointer_size: u64) {
+    let reprs = {
+        let by_index = items
+            .iter()
+            .map(|(index, item)| (*index, item))
+            .collect::<HashMap<_, _>>();
+        items
+            .iter()
+            .filter_map(|(index, item)| match item {
+                Item::Structure(structure) => {
+                    Some((*index, classify(&by_index, pointer_size, structure)))
+                }
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>()
+    };
+
+    for (index, item) in items.iter_mut() {
+        // Structures that couldn't be classified keep the representation they were read with.
+        if let (Item::Structure(structure), Some(&repr)) = (item, reprs.get(index)) {
+            structure.repr = repr;
+        }
+    }
+}
+
+/// Warns about exported functions whose arguments or return value have a type, or a pointer to
+/// one, whose layout isn't stable.
+pub fn warn_unstable_layouts(items: &[(usize, Item)]) {
+    let by_index = items
+        .iter()
+        .map(|(index, item)| (*index, item))
+        .collect::<HashMap<_, _>>();
+    for (_, item) in items {
+        let function = match item {
+            Item::Function(function) => function,
+            _ => continue,
+        };
+
+        let indices = function
+            .ret_type_index
+            .iter()
+            .chain(function.arguments.iter().map(|(_, index)| index));
+        for &index in indices {
+            if let Some(name) = unstable_pointee(&by_index, index) {
+                warn!(
+                    "{} exposes {}, which doesn't have a stable layout",
+                    function.full_name, name
+                );
+            }
+        }
+    }
+}
+
+/// Finds the type a type refers to, looking through typedefs, qualifiers, and one level of
+/// pointers, and returns its name if its layout isn't stable. That's the case for Rust structures
+/// without `#[repr(C)]` or the like, and for Rust's enums, tuples, and fat pointers.
+fn unstable_pointee<'a>(items: &HashMap<usize, &'a Item>, index: usize) -> Option<&'a str> {
+    let mut seen_pointer = false;
+    let mut index = index;
+    loop {
+        match items.get(&index)? {
+            Item::Structure(structure) if structure.repr == Repr::Rust => {
+                return Some(&structure.name)
+            }
+            Item::Enum(ty) if ty.language == Language::Rust => return Some(&ty.name),
+            Item::Tuple(ty) if ty.language == Language::Rust => return Some(&ty.name),
+            Item::Slice(ty) | Item::Str(ty) if ty.language == Language::Rust => {
+                return Some(&ty.name)
+            }
+            Item::TraitObject(ty) if ty.language == Language::Rust => return Some(&ty.name),
+            Item::Typedef(Typedef {
+                type_index: Some(next),
+                ..
+            })
+            | Item::QualifiedType(QualifiedType {
+                type_index: Some(next),
+                ..
+            }) => index = *next,
+            Item::PointerType(ty) if !seen_pointer => {
+                seen_pointer = true;
+                index = ty.type_index?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Works out which `repr` the layout of a structure is consistent with.
+fn classify(items: &HashMap<usize, &Item>, pointer_size: u64, structure: &Structure) -> Repr {
+    // C and C++ never reorder members, and bitfields only come from C.
+    if structure.language != Language::Rust
+        || structure
+            .members
+            .iter()
+            .any(|member| member.bit_size.is_some())
+    {
+        return Repr::C;
+    }
+
+    let sizes = structure
+        .members
+        .iter()
+        .map(|member| size(items, pointer_size, member.type_index))
+        .collect::<Vec<_>>();
+
+    let underaligned = structure.members.iter().any(|member| {
+        member.offset % member.alignment.max(1) != 0 || member.alignment > structure.alignment
+    });
+    if underaligned {
+        return Repr::Packed;
+    }
+
+    let non_zst = structure
+        .members
+        .iter()
+        .zip(&sizes)
+        .filter(|(_, size)| **size != Some(0))
+        .collect::<Vec<_>>();
+    if let [(member, Some(size))] = non_zst.as_slice() {
+        if member.offset == 0 && *size == structure.size && member.alignment == structure.alignment
+        {
+            return Repr::Transparent;
+        }
+    }
+
+    // Lay the members out in declaration order, the way repr(C) would.
+    let mut offset = 0;
+    for (member, size) in structure.members.iter().zip(&sizes) {
+        let expected = align_up(offset, member.alignment);
+        if member.offset != expected {
+            return Repr::Rust;
+        }
+        offset = match size {
+            Some(size) => expected + size,
+            // Without the size, only check that the members aren't reordered.
+            None => expected,
+        };
+    }
+    if sizes.iter().all(|size| size.is_some())
+        && align_up(offset, structure.alignment) != structure.size
+    {
+        return Repr::Rust;
+    }
+    Repr::C
+}
+
+/// Gets the size of the type with the given index, if it's known.
+fn size(items: &HashMap<usize, &Item>, pointer_size: u64, index: usize) -> Option<u64> {
+    match items.get(&index)? {
+        Item::BaseType(ty) => Some(ty.size),
+        Item::PointerType(_) => Some(pointer_size),
+        Item::Structure(ty) | Item::Closure(ty) | Item::ZeroSized(ty) => Some(ty.size),
+        Item::Union(ty) => Some(ty.size),
+        Item::Tuple(ty) => Some(ty.size),
+        Item::Slice(ty) | Item::Str(ty) => Some(ty.size),
+        Item::TraitObject(ty) => Some(ty.size),
+        Item::Enum(ty) => Some(ty.size),
+        Item::Array(ty) => {
+            let stride = match ty.stride {
+                Some(stride) => stride,
+                None => size(items, pointer_size, ty.type_index)?,
+            };
+            // A size that doesn't fit is as good as unknown.
+            ty.dimensions
+                .iter()
+                .try_fold(stride, |size, &count| size.checked_mul(count))
+        }
+        Item::Typedef(Typedef {
+            type_index: Some(index),
+            ..
+        })
+        | Item::QualifiedType(QualifiedType {
+            type_index: Some(index),
+            ..
+        }) => size(items, pointer_size, *index),
+        _ => None,
+    }
+}
+
+/// Rounds `offset` up to a multiple of `alignment`.
+fn align_up(offset: u64, alignment: u64) -> u64 {
+    let alignment = alignment.max(1);
+    offset.div_ceil(alignment) * alignment
+}
@@ -0,0 +1,218 @@
+use anyhow::{anyhow, bail, Result};
+use flate2::Crc;
+use log::warn;
+use object::Object;
+use std::{
+    ffi::OsStr,
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+/// Finds the supplementary file named by the `.gnu_debugaltlink` section of the file at the given
+/// path, which DWZ moves debug info shared between files into. The link is tried as given, or
+/// relative to the file if it's relative, and then the file it names is looked for next to the
+/// file. Only a file with the build ID given by the link is accepted.
+pub fn find_alt_file(path: &Path, file: &[u8]) -> Result<Option<PathBuf>> {
+    let elf = object::File::parse(file)
+        .map_err(|e| anyhow!("{}", e).context("Failed to parse file as ELF"))?;
+    let section = match elf.section_data_by_name(".gnu_debugaltlink") {
+        Some(section) => section,
+        None => return Ok(None),
+    };
+
+    // The section is the path, nul-terminated, followed by the build ID of the file.
+    let (link, expected_id) = match section.iter().position(|&b| b == 0) {
+        Some(end) => (
+            Path::new(OsStr::from_bytes(&section[..end])),
+            &section[end + 1..],
+        ),
+        None => bail!("Invalid .gnu_debugaltlink section"),
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut candidates = vec![dir.join(link)];
+    if let Some(name) = link.file_name() {
+        candidates.push(dir.join(name));
+    }
+
+    for candidate in candidates {
+        if !candidate.is_file() {
+            continue;
+        }
+        let data = fs::read(&candidate)?;
+        let id = object::File::parse(&data)
+            .ok()
+            .and_then(|elf| file_build_id(&elf));
+        if id.as_deref() == Some(expected_id) {
+            return Ok(Some(candidate));
+        }
+        warn!(
+            "Ignoring {}, since its build ID doesn't match .gnu_debugaltlink",
+            candidate.display()
+        );
+    }
+
+    // The file's own units are still usable without it.
+    warn!(
+        "Couldn't find the supplementary file {}, which can be given with --alt-file",
+        link.display()
+    );
+    Ok(None)
+}
+
+/// Finds the separate file with the debug info of the stripped file at the given path, from the
+/// build ID in its `.note.gnu.build-id` section or the name in its `.gnu_debuglink` section. Files
+/// are looked for in the given debug directories, like `/usr/lib/debug`, the way GDB does.
+pub fn find_debug_file(
+    path: &Path,
+    file: &[u8],
+    debug_dirs: &[PathBuf],
+) -> Result<Option<PathBuf>> {
+    let elf = object::File::parse(file)
+        .map_err(|e| anyhow!("{}", e).context("Failed to parse file as ELF"))?;
+    if elf.section_by_name(".debug_info").is_some() || elf.section_by_name(".zdebug_info").is_some()
+    {
+        return Ok(None);
+    }
+    let read_u32 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if elf.is_little_endian() {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+
+    if let Some(build_id) = file_build_id(&elf) {
+        if build_id.len() >= 2 {
+            let hex = build_id
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+            for dir in debug_dirs {
+                let candidate = dir
+                    .join(".build-id")
+                    .join(&hex[..2])
+                    .join(format!("{}.debug", &hex[2..]));
+                if candidate.is_file() {
+                    return Ok(Some(candidate));
+                }
+            }
+        }
+    }
+
+    if let Some(section) = elf.section_data_by_name(".gnu_debuglink") {
+        // The section is the name of the file, nul-terminated and padded to four bytes, followed
+        // by the CRC of the file.
+        let end = match section.iter().position(|&b| b == 0) {
+            Some(end) if ((end + 4) & !3) + 4 <= section.len() => end,
+            _ => bail!("Invalid .gnu_debuglink section"),
+        };
+        let name = Path::new(OsStr::from_bytes(&section[..end]));
+        let crc = read_u32(&section[(end + 4) & !3..]);
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut candidates = vec![dir.join(name), dir.join(".debug").join(name)];
+        if let Ok(dir) = dir.canonicalize() {
+            for debug_dir in debug_dirs {
+                candidates.push(
+                    debug_dir
+                        .join(dir.strip_prefix("/").unwrap_or(&dir))
+                        .join(name),
+                );
+            }
+        }
+        candidates.extend(debug_dirs.iter().map(|debug_dir| debug_dir.join(name)));
+
+        for candidate in candidates {
+            // The stripped file may have the same name as its debug file.
+            if !candidate.is_file() || same_file(path, &candidate) {
+                continue;
+            }
+            let data = fs::read(&candidate)?;
+            if crc32(&data) == crc {
+                return Ok(Some(candidate));
+            }
+            warn!(
+                "Ignoring {}, since its CRC doesn't match .gnu_debuglink",
+                candidate.display()
+            );
+        }
+    }
+
+    warn!(
+        "Couldn't find the separate debug info of {}",
+        path.display()
+    );
+    Ok(None)
+}
+
+/// Gets the build ID of a file from its `.note.gnu.build-id` section.
+fn file_build_id(elf: &object::File) -> Option<Vec<u8>> {
+    let little_endian = elf.is_little_endian();
+    let read_u32 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+    build_id(&elf.section_data_by_name(".note.gnu.build-id")?, read_u32)
+}
+
+/// Gets the build ID from the notes in a `.note.gnu.build-id` section.
+fn build_id(mut notes: &[u8], read_u32: impl Fn(&[u8]) -> u32) -> Option<Vec<u8>> {
+    let align = |n: usize| (n + 3) & !3;
+    while notes.len() >= 12 {
+        let name_size = read_u32(&notes[0..]) as usize;
+        let desc_size = read_u32(&notes[4..]) as usize;
+        let kind = read_u32(&notes[8..]);
+        let desc_start = 12 + align(name_size);
+        let desc_end = desc_start + desc_size;
+        if desc_end > notes.len() {
+            return None;
+        }
+        if kind == NT_GNU_BUILD_ID && &notes[12..12 + name_size] == b"GNU\0" {
+            return Some(notes[desc_start..desc_end].to_vec());
+        }
+        notes = &notes[align(desc_end).min(notes.len())..];
+    }
+    None
+}
+
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// Computes the CRC used by `.gnu_debuglink`, which is the same as zlib's.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Finds the `.dwp` package with the split debug info of the file at the given path, which is
+/// named after it. If the file's debug info is in a separate debug file, the package is usually
+/// next to that instead, named after either file.
+pub fn find_dwp_file(path: &Path, debug_path: Option<&Path>) -> Option<PathBuf> {
+    let with_dwp = |path: &Path| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".dwp");
+        PathBuf::from(name)
+    };
+
+    let mut candidates = vec![with_dwp(path)];
+    if let Some(debug_path) = debug_path {
+        candidates.push(with_dwp(debug_path));
+        if let (Some(dir), Some(name)) = (debug_path.parent(), path.file_name()) {
+            candidates.push(with_dwp(&dir.join(name)));
+        }
+    }
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
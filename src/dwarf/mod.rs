@@ -1,85 +1,404 @@
+mod array_type;
 mod base_type;
+mod enumeration;
 mod function;
+mod layout;
+mod links;
 mod pointer_type;
+mod qualified_type;
+mod sections;
+mod split;
 mod structure;
+mod subroutine_type;
+mod template_parameter;
+mod typedef;
+mod variable;
 
-use crate::item::Item;
-use anyhow::{anyhow, Context, Result};
+pub use self::links::{find_alt_file, find_debug_file, find_dwp_file};
+
+use self::sections::Sections;
+use crate::item::{Item, Language, Union};
+use anyhow::{anyhow, bail, Context, Result};
 use fallible_iterator::FallibleIterator;
 use gimli::{
-    AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, EntriesTreeNode, RunTimeEndian,
-    Unit,
+    AttributeValue, DebugInfoOffset, DebugTypeSignature, DebugTypesOffset,
+    DebuggingInformationEntry, DwLang, Dwarf, DwarfFileType, EndianSlice, EntriesTreeNode,
+    EvaluationResult, Expression, Location, Piece, Reader, RunTimeEndian, Section, Unit,
+    UnitOffset, UnitSectionOffset, UnitType,
 };
 use log::{debug, error, trace};
 use object::Object;
-use std::{borrow::Cow, str};
+use rustc_demangle::demangle;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    str,
+};
 
-pub fn get_items(file: &[u8]) -> Result<Vec<(usize, Item)>> {
-    let elf = object::File::parse(&file)
+/// The files that the debug info of a library may be spread across, besides the library itself.
+#[derive(Debug, Default)]
+pub struct DebugFiles {
+    /// The separate file with the debug info of a stripped library.
+    pub debug_file: Option<Vec<u8>>,
+    /// The supplementary file that DWZ moves debug info shared between files into.
+    pub sup_file: Option<Vec<u8>>,
+    /// The package of the split units of the library, made by `dwp`.
+    pub dwp_file: Option<Vec<u8>>,
+    /// The directories to look for `.dwo` files in, if they aren't in the compilation directory.
+    pub dwo_dirs: Vec<PathBuf>,
+}
+
+/// Gets the items described by the debug info in the given file, paired with their indices. If the
+/// file was stripped, the debug info is read from its separate debug file instead. Split units are
+/// read from the `.dwp` package if there is one, and otherwise from the `.dwo` files named by the
+/// skeleton units.
+///
+/// An item's index is the offset of its DIE in the `.debug_info` section, so it is unique across
+/// all the units in the file. Items from type units in the `.debug_types` section are numbered
+/// after the end of `.debug_info`, then items from the supplementary file, then items from each
+/// split file in turn. The `*_index` fields of items refer to these indices. Exported functions
+/// that the debug info doesn't describe get stub items, numbered after all of those.
+pub fn get_items(file: &[u8], debug_files: &DebugFiles) -> Result<Vec<(usize, Item)>> {
+    let elf = object::ElfFile::parse(file)
         .map_err(|e| anyhow!("{}", e).context("Failed to parse file as ELF"))?;
     let endianess = if elf.is_little_endian() {
         RunTimeEndian::Little
     } else {
         RunTimeEndian::Big
     };
+    let debug_sections = Sections::parse(debug_files.debug_file.as_deref().unwrap_or(file))
+        .context("Failed to read sections of debug file")?;
+    let sup_sections = match &debug_files.sup_file {
+        Some(sup_file) => Some(
+            Sections::parse(sup_file).context("Failed to read sections of supplementary file")?,
+        ),
+        None => None,
+    };
+    let sup_section = |name| -> Result<_> {
+        Ok(match &sup_sections {
+            Some(sup_sections) => sup_sections.data(name)?,
+            None => None,
+        }
+        .unwrap_or(Cow::Borrowed(&[][..])))
+    };
     let dwarf = Dwarf::load(
         |section| -> Result<_> {
-            Ok(elf
-                .section_data_by_name(section.name())
+            Ok(debug_sections
+                .data(section.name())?
                 .unwrap_or(Cow::Borrowed(&[][..])))
         },
-        |_section| Ok(Cow::Borrowed(&[][..])),
+        |section| sup_section(section.name()),
     )
     .context("Failed to parse debug info")?;
-    let dwarf = dwarf.borrow(|section| EndianSlice::new(&section, endianess));
+    let dwarf = dwarf.borrow(|section| EndianSlice::new(section, endianess));
+    let sup_dwarf = Dwarf::load(
+        |section| sup_section(section.name()),
+        |_section| Ok(Cow::Borrowed(&[][..])),
+    )
+    .context("Failed to parse debug info of supplementary file")?;
+    let sup_dwarf = sup_dwarf.borrow(|section| EndianSlice::new(section, endianess));
 
-    dwarf
-        .units()
-        .map_err(|err| anyhow::Error::from(err).context("Error getting next unit"))
-        .flat_map(|header| {
-            let unit = dwarf.unit(header).context("Failed to call unit()")?;
+    let sup_base = dwarf.debug_info.reader().len() + dwarf.debug_types.reader().len();
+    let mut indices = Indices {
+        base: 0,
+        sup_base,
+        type_units: HashMap::new(),
+    };
+    let mut sup_indices = Indices {
+        base: sup_base,
+        sup_base,
+        type_units: HashMap::new(),
+    };
+    let units = load_units(&dwarf, &mut indices)?;
+    let sup_units = load_units(&sup_dwarf, &mut sup_indices)?;
 
-            let mut items = Vec::new();
+    let skeletons = split::find_skeletons(&dwarf, &units)?;
+    let dwo_files = match debug_files.dwp_file {
+        Some(_) => Vec::new(),
+        None => split::read_dwo_files(&skeletons, &debug_files.dwo_dirs)?,
+    };
+    let dwo_sections = dwo_files
+        .iter()
+        .map(|(dwo_file, _)| Sections::parse(dwo_file).context("Failed to read sections of .dwo"))
+        .collect::<Result<Vec<_>>>()?;
+    let dwp_sections = match &debug_files.dwp_file {
+        Some(dwp_file) => {
+            Some(Sections::parse(dwp_file).context("Failed to read sections of .dwp")?)
+        }
+        None => None,
+    };
+    let mut split_files = Vec::new();
+    for (sections, (_, addr_base)) in dwo_sections.iter().zip(&dwo_files) {
+        split_files.push(split::from_dwo(sections, *addr_base)?);
+    }
+    if let Some(dwp_sections) = &dwp_sections {
+        split_files.extend(split::from_dwp(dwp_sections, endianess, &skeletons)?);
+    }
+    let debug_addr = debug_sections
+        .data(".debug_addr")?
+        .unwrap_or(Cow::Borrowed(&[][..]));
+    let split_dwarfs = split_files
+        .iter()
+        .map(|split_file| {
+            let mut split_dwarf = Dwarf::load(
+                |section| -> Result<_> { Ok(split_file.section(section.name(), &debug_addr)) },
+                |_section| Ok(Cow::Borrowed(&[][..])),
+            )
+            .context("Failed to parse split debug info")?;
+            split_dwarf.file_type = DwarfFileType::Dwo;
+            Ok(split_dwarf)
+        })
+        .collect::<Result<Vec<_>>>()?;
 
+    let mut base =
+        sup_base + sup_dwarf.debug_info.reader().len() + sup_dwarf.debug_types.reader().len();
+    let mut files = vec![(dwarf, indices, units), (sup_dwarf, sup_indices, sup_units)];
+    for (split_dwarf, split_file) in split_dwarfs.iter().zip(&split_files) {
+        let split_dwarf = split_dwarf.borrow(|section| EndianSlice::new(section, endianess));
+        let mut indices = Indices {
+            base,
+            sup_base,
+            type_units: HashMap::new(),
+        };
+        let mut units = load_units(&split_dwarf, &mut indices)?;
+        for unit in &mut units {
+            split_file.fix_unit(unit);
+        }
+        base += split_dwarf.debug_info.reader().len() + split_dwarf.debug_types.reader().len();
+        files.push((split_dwarf, indices, units));
+    }
+
+    // Split units refer to type units by signature, which may be in other split files.
+    let type_units = files
+        .iter()
+        .flat_map(|(_, indices, _)| indices.type_units.clone())
+        .collect::<HashMap<_, _>>();
+    for (_, indices, _) in &mut files {
+        indices.type_units = type_units.clone();
+    }
+
+    // Partial units don't say which language they're in, so they get it from the units that
+    // import them, which may themselves be partial units.
+    let mut imported_languages = HashMap::new();
+    loop {
+        let count = imported_languages.len();
+        for (dwarf, indices, units) in &files {
+            for unit in units.iter() {
+                find_imports(dwarf, unit, indices, &mut imported_languages)?;
+            }
+        }
+        if imported_languages.len() == count {
+            break;
+        }
+    }
+
+    let mut items = Vec::new();
+    for (dwarf, indices, units) in &files {
+        for unit in units.iter() {
             let mut tree = unit
                 .entries_tree(None)
                 .context("Failed to get entries tree")?;
             let node = tree.root().context("Failed to get root of entries tree")?;
-            handle_node(&dwarf, &unit, &mut Vec::new(), &mut items, node)?;
-            Ok(fallible_iterator::convert(items.into_iter().map(Ok)))
+            if split::is_skeleton(node.entry())? {
+                continue;
+            }
+            let index = global_offset(dwarf, unit, indices, UnitOffset(0))?;
+            let language = match unit_language(node.entry())? {
+                Some(language) => language,
+                None if imported_languages.contains_key(&index) => imported_languages[&index],
+                None if node.entry().tag() == gimli::DW_TAG_partial_unit => {
+                    debug!("Skipping partial unit at 0x{:x} that isn't imported", index);
+                    continue;
+                }
+                None => {
+                    if let Some(name) = node.entry().attr_value(gimli::DW_AT_name)? {
+                        let name = dwarf.attr_string(unit, name)?;
+                        let name = str::from_utf8(&name)?;
+                        error!(
+                            "The compilation unit {:?} isn't in a supported language.",
+                            name
+                        );
+                    } else {
+                        error!("The compilation unit isn't in a supported language.");
+                    }
+                    continue;
+                }
+            };
+            handle_node(
+                dwarf,
+                unit,
+                indices,
+                language,
+                &mut Vec::new(),
+                &mut items,
+                node,
+            )?;
+        }
+    }
+
+    // Functions can be external in the debug info but still not be exported, if they were inlined
+    // or internalized by LTO, and Rust statics are only exported from a cdylib if they're
+    // #[no_mangle]. Files without dynamic symbols at all are taken at their word.
+    let symbols = function::exported_symbols(&elf);
+    let statics = variable::exported_symbols(&elf);
+    if !symbols.is_empty() || !statics.is_empty() {
+        items.retain(|(_, item)| match item {
+            Item::Function(func) if !symbols.contains_key(&func.linkage_name) => {
+                debug!("{} isn't exported, so it's skipped", func.full_name);
+                false
+            }
+            Item::Static(var)
+                if !statics.contains(var.linkage_name.as_ref().unwrap_or(&var.name)) =>
+            {
+                debug!("{} isn't exported, so it's skipped", var.full_name);
+                false
+            }
+            _ => true,
+        });
+    }
+
+    // Exported functions without debug info still get stubs, so bindings can type them by hand.
+    let described = items
+        .iter()
+        .filter_map(|(_, item)| match item {
+            Item::Function(func) => Some(func.linkage_name.as_str()),
+            _ => None,
         })
-        .collect()
+        .collect::<HashSet<_>>();
+    let mut stubs = symbols
+        .iter()
+        .filter(|(name, _)| !described.contains(name.as_str()))
+        .filter_map(|(name, symbol)| function::from_symbol(name, symbol))
+        .collect::<Vec<_>>();
+    stubs.sort_by(|a, b| a.linkage_name.cmp(&b.linkage_name));
+    for (i, func) in stubs.into_iter().enumerate() {
+        debug!(
+            "{} has no debug info, so its signature is unknown",
+            func.full_name
+        );
+        items.push((base + i, Item::Function(func)));
+    }
+
+    let pointer_size = if elf.is_64() { 8 } else { 4 };
+    layout::infer_alignments(&mut items, pointer_size);
+    layout::classify_structures(&mut items, pointer_size);
+    layout::warn_unstable_layouts(&items);
+    for (_, item) in &mut items {
+        match item {
+            Item::Function(func) => func.symbol = symbols.get(&func.linkage_name).cloned(),
+            Item::Static(var) => {
+                if let Some(address) = var.address {
+                    var.mutable = variable::is_writable(&elf, address);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(items)
+}
+
+/// How DIE offsets in a file are turned into item indices.
+struct Indices {
+    /// The index of the start of the file's `.debug_info` section.
+    base: usize,
+    /// The index of the start of the supplementary file's `.debug_info` section.
+    sup_base: usize,
+    /// The indices of the type DIEs of the file's type units, by signature.
+    type_units: HashMap<DebugTypeSignature, usize>,
+}
+
+/// Gets the compilation, partial, and type units in a file, and records the signatures of the type
+/// units.
+fn load_units<'a>(
+    dwarf: &Dwarf<EndianSlice<'a, RunTimeEndian>>,
+    indices: &mut Indices,
+) -> Result<Vec<Unit<EndianSlice<'a, RunTimeEndian>>>> {
+    let mut headers = dwarf
+        .units()
+        .map_err(|err| anyhow::Error::from(err).context("Error getting next unit"))
+        .collect::<Vec<_>>()?;
+    let mut iter = dwarf.type_units();
+    while let Some(header) = iter.next().context("Error getting next type unit")? {
+        headers.push(header);
+    }
+
+    let mut units = Vec::new();
+    for header in headers {
+        let unit = dwarf.unit(header).context("Failed to call unit()")?;
+        // DWARF 4 type units are in .debug_types, and DWARF 5 ones are in .debug_info.
+        match unit.header.type_() {
+            UnitType::Type {
+                type_signature,
+                type_offset,
+            }
+            | UnitType::SplitType {
+                type_signature,
+                type_offset,
+            } => {
+                let offset = global_offset(dwarf, &unit, indices, type_offset)?;
+                indices.type_units.insert(type_signature, offset);
+            }
+            _ => {}
+        }
+        units.push(unit);
+    }
+    Ok(units)
+}
+
+/// Records the language of the units imported by a unit whose language is known, by the index of
+/// the start of the imported unit.
+fn find_imports(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    imported_languages: &mut HashMap<usize, Language>,
+) -> Result<()> {
+    let mut tree = unit.entries_tree(None)?;
+    let node = tree.root()?;
+    let index = global_offset(dwarf, unit, indices, UnitOffset(0))?;
+    let language = match unit_language(node.entry())? {
+        Some(language) => language,
+        None => match imported_languages.get(&index) {
+            Some(language) => *language,
+            None => return Ok(()),
+        },
+    };
+
+    let mut iter = node.children();
+    while let Some(node) = iter.next()? {
+        if node.entry().tag() != gimli::DW_TAG_imported_unit {
+            continue;
+        }
+        if let Some(value) = node.entry().attr_value(gimli::DW_AT_import)? {
+            let imported = type_index(dwarf, unit, indices, value)?;
+            imported_languages.entry(imported).or_insert(language);
+        }
+    }
+    Ok(())
 }
 
 fn handle_node(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    language: Language,
     module: &mut Vec<String>,
     items: &mut Vec<(usize, Item)>,
     node: EntriesTreeNode<EndianSlice<RunTimeEndian>>,
 ) -> Result<()> {
-    let offset = node.entry().offset().0;
+    let offset = global_offset(dwarf, unit, indices, node.entry().offset())?;
     match node.entry().tag() {
-        gimli::DW_TAG_compile_unit => {
-            if node.entry().attr_value(gimli::DW_AT_language)?
-                != Some(AttributeValue::Language(gimli::DW_LANG_Rust))
-            {
-                if let Some(name) = node.entry().attr_value(gimli::DW_AT_name)? {
-                    let name = dwarf.attr_string(unit, name)?;
-                    let name = str::from_utf8(&name)?;
-                    error!("The compilation unit {:?} doesn't appear to be Rust.", name);
-                } else {
-                    error!("The compilation unit doesn't appear to be Rust.");
-                }
-            } else {
-                let mut iter = node.children();
-                while let Some(node) = iter.next()? {
-                    if let Err(err) = handle_node(dwarf, unit, module, items, node) {
-                        error!("{}", err);
-                    }
+        gimli::DW_TAG_compile_unit | gimli::DW_TAG_partial_unit | gimli::DW_TAG_type_unit => {
+            let mut iter = node.children();
+            while let Some(node) = iter.next()? {
+                if let Err(err) = handle_node(dwarf, unit, indices, language, module, items, node) {
+                    error!("{}", err);
                 }
             }
         }
+        // Imported units are read on their own, so their items are only added once.
+        gimli::DW_TAG_imported_unit => {}
         gimli::DW_TAG_namespace => {
             if let Some(name) = node.entry().attr_value(gimli::DW_AT_name)? {
                 let name = str::from_utf8(&dwarf.attr_string(unit, name)?)?.to_string();
@@ -88,7 +407,7 @@ fn handle_node(
 
             let mut iter = node.children();
             while let Some(node) = iter.next()? {
-                if let Err(err) = handle_node(dwarf, unit, module, items, node) {
+                if let Err(err) = handle_node(dwarf, unit, indices, language, module, items, node) {
                     error!("{}", err);
                 }
             }
@@ -96,7 +415,8 @@ fn handle_node(
             module.pop();
         }
         gimli::DW_TAG_subprogram => {
-            let func = function::from_subprogram(dwarf, unit, &module, node.entry())?;
+            let func =
+                function::from_subprogram(dwarf, unit, indices, module, language, node.entry())?;
             let mut func = if let Some(func) = func {
                 func
             } else {
@@ -105,28 +425,145 @@ fn handle_node(
 
             let mut iter = node.children();
             while let Some(node) = iter.next()? {
-                function::modify(dwarf, unit, &mut func, node.entry())?;
+                function::modify(dwarf, unit, indices, &mut func, node.entry())?;
             }
 
             items.push((offset, Item::Function(func)));
         }
+        gimli::DW_TAG_variable => {
+            let var =
+                variable::from_variable(dwarf, unit, indices, module, language, node.entry())?;
+            if let Some(var) = var {
+                items.push((offset, Item::Static(var)));
+            }
+        }
         gimli::DW_TAG_base_type => {
-            let ty = base_type::from_base_type(dwarf, unit, &module, node.entry())?;
+            let ty = base_type::from_base_type(dwarf, unit, module, language, node.entry())?;
             items.push((offset, Item::BaseType(ty)));
         }
-        gimli::DW_TAG_pointer_type => {
-            let ty = pointer_type::from_pointer_type(dwarf, unit, &module, node.entry())?;
+        gimli::DW_TAG_pointer_type
+        | gimli::DW_TAG_reference_type
+        | gimli::DW_TAG_rvalue_reference_type => {
+            let ty = pointer_type::from_pointer_type(
+                dwarf,
+                unit,
+                indices,
+                module,
+                language,
+                node.entry(),
+            )?;
             items.push((offset, Item::PointerType(ty)));
         }
-        gimli::DW_TAG_structure_type => {
-            let mut ty = structure::from_structure_type(dwarf, unit, &module, node.entry())?;
+        gimli::DW_TAG_subroutine_type => {
+            let mut ty = subroutine_type::from_subroutine_type(
+                dwarf,
+                unit,
+                indices,
+                module,
+                language,
+                node.entry(),
+            )?;
+
+            let mut iter = node.children();
+            while let Some(node) = iter.next()? {
+                subroutine_type::modify(dwarf, unit, indices, &mut ty, node.entry())?;
+            }
+
+            items.push((offset, Item::FunctionPointer(ty)));
+        }
+        gimli::DW_TAG_typedef => {
+            let ty = typedef::from_typedef(dwarf, unit, indices, module, language, node.entry())?;
+            items.push((offset, Item::Typedef(ty)));
+        }
+        gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type | gimli::DW_TAG_restrict_type => {
+            let ty = qualified_type::from_qualified_type(
+                dwarf,
+                unit,
+                indices,
+                module,
+                language,
+                node.entry(),
+            )?;
+            items.push((offset, Item::QualifiedType(ty)));
+        }
+        gimli::DW_TAG_array_type => {
+            let mut ty =
+                array_type::from_array_type(dwarf, unit, indices, module, language, node.entry())?;
 
             let mut iter = node.children();
             while let Some(node) = iter.next()? {
-                structure::modify(dwarf, unit, module, items, &mut ty, node)?;
+                array_type::modify(dwarf, unit, &mut ty, node.entry())?;
             }
 
-            items.push((offset, Item::Structure(ty)));
+            items.push((offset, Item::Array(ty)));
+        }
+        gimli::DW_TAG_structure_type | gimli::DW_TAG_class_type => {
+            if structure::is_declaration(node.entry())? {
+                let ty = structure::from_declaration(dwarf, unit, module, language, node.entry())?;
+                items.push((offset, Item::Opaque(ty)));
+                return Ok(());
+            }
+
+            let mut ty = structure::from_structure_type(
+                dwarf,
+                unit,
+                indices,
+                module,
+                language,
+                node.entry(),
+            )?;
+
+            let mut enum_ = None;
+            let mut iter = node.children();
+            while let Some(node) = iter.next()? {
+                if node.entry().tag() == gimli::DW_TAG_variant_part {
+                    enum_ = Some(enumeration::from_variant_part(
+                        dwarf, unit, indices, &ty, node,
+                    )?);
+                } else {
+                    structure::modify(dwarf, unit, indices, module, items, &mut ty, node)?;
+                }
+            }
+
+            if let Some(enum_) = enum_ {
+                items.push((offset, Item::Enum(enum_)));
+            } else {
+                items.push((offset, structure::into_item(ty)));
+            }
+        }
+        gimli::DW_TAG_union_type => {
+            if structure::is_declaration(node.entry())? {
+                let ty = structure::from_declaration(dwarf, unit, module, language, node.entry())?;
+                items.push((offset, Item::Opaque(ty)));
+                return Ok(());
+            }
+
+            let mut ty = structure::from_structure_type(
+                dwarf,
+                unit,
+                indices,
+                module,
+                language,
+                node.entry(),
+            )?;
+
+            let mut iter = node.children();
+            while let Some(node) = iter.next()? {
+                structure::modify(dwarf, unit, indices, module, items, &mut ty, node)?;
+            }
+
+            items.push((
+                offset,
+                Item::Union(Union {
+                    name: ty.name,
+                    module: ty.module,
+                    language: ty.language,
+                    size: ty.size,
+                    alignment: ty.alignment,
+                    alignment_inferred: ty.alignment_inferred,
+                    members: ty.members,
+                }),
+            ));
         }
         tag => {
             debug!("Unsupported tag: {}", tag);
@@ -136,6 +573,212 @@ fn handle_node(
     Ok(())
 }
 
+// Language codes registered after DWARF 5 was published, which gimli doesn't know about yet.
+const DW_LANG_CPP17: DwLang = DwLang(0x002a);
+const DW_LANG_CPP20: DwLang = DwLang(0x002b);
+const DW_LANG_C17: DwLang = DwLang(0x002c);
+
+/// Gets the language of a unit from its root DIE, or `None` if it isn't one that's supported.
+fn unit_language(
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<Option<Language>> {
+    Ok(match die.attr_value(gimli::DW_AT_language)? {
+        Some(AttributeValue::Language(gimli::DW_LANG_Rust)) => Some(Language::Rust),
+        Some(AttributeValue::Language(gimli::DW_LANG_C89))
+        | Some(AttributeValue::Language(gimli::DW_LANG_C))
+        | Some(AttributeValue::Language(gimli::DW_LANG_C99))
+        | Some(AttributeValue::Language(gimli::DW_LANG_C11))
+        | Some(AttributeValue::Language(DW_LANG_C17)) => Some(Language::C),
+        Some(AttributeValue::Language(gimli::DW_LANG_C_plus_plus))
+        | Some(AttributeValue::Language(gimli::DW_LANG_C_plus_plus_03))
+        | Some(AttributeValue::Language(gimli::DW_LANG_C_plus_plus_11))
+        | Some(AttributeValue::Language(gimli::DW_LANG_C_plus_plus_14))
+        | Some(AttributeValue::Language(DW_LANG_CPP17))
+        | Some(AttributeValue::Language(DW_LANG_CPP20)) => Some(Language::Cpp),
+        _ => None,
+    })
+}
+
+/// Converts an offset within a unit to an index that is unique across all the units in the file.
+fn global_offset(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    offset: UnitOffset,
+) -> Result<usize> {
+    match unit.header.offset() {
+        UnitSectionOffset::DebugInfoOffset(DebugInfoOffset(base)) => {
+            Ok(indices.base + base + offset.0)
+        }
+        UnitSectionOffset::DebugTypesOffset(DebugTypesOffset(base)) => {
+            Ok(indices.base + dwarf.debug_info.reader().len() + base + offset.0)
+        }
+    }
+}
+
+/// Finds the unit containing the DIE at the given offset in `.debug_info`, and the offset of the DIE
+/// in that unit. This is for `DW_FORM_ref_addr` references, which may point into other units.
+fn unit_containing<'a>(
+    dwarf: &Dwarf<EndianSlice<'a, RunTimeEndian>>,
+    offset: DebugInfoOffset,
+) -> Result<(Unit<EndianSlice<'a, RunTimeEndian>>, UnitOffset)> {
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        if let Some(unit_offset) = offset.to_unit_offset(&header) {
+            return Ok((dwarf.unit(header)?, unit_offset));
+        }
+    }
+    bail!("No unit contains the DIE at 0x{:x}", offset.0)
+}
+
+/// Gets the names of the namespaces and types enclosing the DIE at the given offset, which make up
+/// its module. `module` is normally built up while walking the tree, but this is needed for DIEs
+/// that are referred to from elsewhere.
+fn enclosing_module(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    offset: UnitOffset,
+) -> Result<Vec<String>> {
+    let mut path = Vec::new();
+    let mut depth = 0;
+    let mut entries = unit.entries();
+    while let Some((delta, entry)) = entries.next_dfs()? {
+        depth += delta;
+        path.truncate(depth as usize);
+        if entry.offset() == offset {
+            return Ok(path.into_iter().flatten().collect());
+        }
+
+        let name = match entry.tag() {
+            gimli::DW_TAG_namespace
+            | gimli::DW_TAG_structure_type
+            | gimli::DW_TAG_class_type
+            | gimli::DW_TAG_union_type => match entry.attr_value(gimli::DW_AT_name)? {
+                Some(name) => Some(str::from_utf8(&dwarf.attr_string(unit, name)?)?.to_string()),
+                None => None,
+            },
+            _ => None,
+        };
+        path.push(name);
+    }
+    bail!("No DIE at 0x{:x}", offset.0)
+}
+
+/// Gets the offset of a member from its `DW_AT_data_member_location` attribute, which is either a
+/// constant or a location expression.
+fn member_offset(
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    value: AttributeValue<EndianSlice<RunTimeEndian>>,
+) -> Result<u64> {
+    let expr = match value {
+        AttributeValue::Exprloc(expr) => expr,
+        AttributeValue::Block(data) => Expression(data),
+        val => {
+            return val
+                .udata_value()
+                .ok_or_else(|| anyhow!("Invalid DW_AT_data_member_location: {:?}", val))
+        }
+    };
+
+    // The expression computes the address of the member from the address of the structure, so
+    // starting from zero gives the offset.
+    let mut eval = expr.evaluation(unit.encoding());
+    eval.set_initial_value(0);
+    match eval.evaluate()? {
+        EvaluationResult::Complete => {}
+        result => bail!(
+            "Unsupported DW_AT_data_member_location expression, requires {:?}",
+            result
+        ),
+    }
+    match eval.result().as_slice() {
+        [Piece {
+            location: Location::Address { address },
+            ..
+        }] => Ok(*address),
+        pieces => bail!(
+            "Unsupported DW_AT_data_member_location expression, evaluates to {:?}",
+            pieces
+        ),
+    }
+}
+
+/// Returns whether the demangled name of a function or static belongs to the standard library or
+/// compiler internals, rather than to the library bindings are being generated for.
+fn is_library_name(full_name: &str) -> bool {
+    // v0 mangled names keep the disambiguator of each crate, as in `std[1a2b3c4d]::io`.
+    let krate = match full_name.find("::") {
+        Some(end) => full_name[..end].split('[').next().unwrap_or(""),
+        None => "",
+    };
+    // Likely nix some of these.
+    [
+        "addr2line",
+        "adler",
+        "adler2",
+        "alloc",
+        "backtrace",
+        "compiler_builtins",
+        "core",
+        "gimli",
+        "hashbrown",
+        "libc",
+        "memchr",
+        "miniz_oxide",
+        "object",
+        "panic_unwind",
+        "rustc_demangle",
+        "std",
+        "std_detect",
+        "unwind",
+    ]
+    .contains(&krate)
+        || full_name.starts_with("rust_")
+        || full_name.starts_with("<")
+        || full_name.starts_with("__")
+}
+
+/// Gets the fully qualified name of a function or static. Rust's is recovered by demangling, since
+/// the module path doesn't include impl blocks, and leaves off the hash, as stubs' names do. C has
+/// no namespaces at all.
+fn full_name(
+    language: Language,
+    module: &[String],
+    name: Option<&String>,
+    linkage_name: &str,
+) -> String {
+    match (language, name) {
+        (Language::C, Some(name)) => name.clone(),
+        (Language::Cpp, Some(name)) => module
+            .iter()
+            .chain(Some(name))
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join("::"),
+        _ => format!("{:#}", demangle(linkage_name)),
+    }
+}
+
+/// Gets the index of the type referred to by a `DW_AT_type` attribute.
+fn type_index(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    value: AttributeValue<EndianSlice<RunTimeEndian>>,
+) -> Result<usize> {
+    match value {
+        AttributeValue::UnitRef(offset) => global_offset(dwarf, unit, indices, offset),
+        AttributeValue::DebugInfoRef(DebugInfoOffset(offset)) => Ok(indices.base + offset),
+        AttributeValue::DebugInfoRefSup(DebugInfoOffset(offset)) => Ok(indices.sup_base + offset),
+        AttributeValue::DebugTypesRef(signature) => indices
+            .type_units
+            .get(&signature)
+            .copied()
+            .ok_or_else(|| anyhow!("No type unit with signature 0x{:x}", signature.0)),
+        val => bail!("Unexpected DW_AT_type value: {:?}", val),
+    }
+}
+
 fn dump_die(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
@@ -1,5 +1,8 @@
-use crate::item::PointerType;
-use anyhow::{anyhow, bail, Result};
+use crate::{
+    dwarf::{type_index, Indices},
+    item::{Language, PointerKind, PointerType},
+};
+use anyhow::{bail, Result};
 use gimli::{
     AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, RunTimeEndian, Unit, UnitOffset,
 };
@@ -8,11 +11,14 @@ use std::str;
 pub fn from_pointer_type(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
     module: &[String],
+    language: Language,
     die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
 ) -> Result<PointerType> {
     let mut name = None;
     let mut ty = None;
+    let mut pointee_const = false;
 
     let mut attrs = die.attrs();
     while let Some(attr) = attrs.next()? {
@@ -21,18 +27,73 @@ pub fn from_pointer_type(
                 name = Some(str::from_utf8(&dwarf.attr_string(unit, attr.value())?)?.to_string());
             }
             gimli::DW_AT_type => {
-                ty = Some(match attr.value() {
-                    AttributeValue::UnitRef(UnitOffset(n)) => n,
-                    val => bail!("Unexpected DW_AT_type value: {:?}", val),
-                });
+                ty = Some(type_index(dwarf, unit, indices, attr.value())?);
+                if let AttributeValue::UnitRef(offset) = attr.value() {
+                    pointee_const = is_const(unit, offset)?;
+                }
             }
             _ => {}
         }
     }
 
+    let kind = match die.tag() {
+        gimli::DW_TAG_pointer_type => PointerKind::Raw,
+        gimli::DW_TAG_reference_type => PointerKind::Reference,
+        gimli::DW_TAG_rvalue_reference_type => PointerKind::RvalueReference,
+        tag => bail!("Not a pointer type: {}", tag),
+    };
+
+    // Rust emits references, boxes, and function pointers as DW_TAG_pointer_type, so these are
+    // told apart by name. C and C++ pointers are unnamed, and their constness is on the pointee.
+    let (kind, mutable, nullable) = match name.as_deref() {
+        Some(name) if name.starts_with("&mut ") => (PointerKind::Reference, true, false),
+        Some(name) if name.starts_with('&') => (PointerKind::Reference, false, false),
+        Some(name) if name.starts_with("*const ") => (kind, false, true),
+        Some(name) if name.starts_with("alloc::boxed::Box<") => (kind, true, false),
+        Some(name) if is_fn_pointer_name(name) => (kind, false, false),
+        _ => (kind, !pointee_const, kind == PointerKind::Raw),
+    };
+
     Ok(PointerType {
-        name: name.ok_or_else(|| anyhow!("Missing DW_AT_name"))?,
+        name,
         module: module.to_vec(),
-        type_index: ty.ok_or_else(|| anyhow!("Missing DW_AT_type"))?,
+        language,
+        type_index: ty,
+        kind,
+        mutable,
+        nullable,
     })
 }
+
+/// Returns whether the type at the given offset is const, looking through other qualifiers.
+fn is_const(unit: &Unit<EndianSlice<RunTimeEndian>>, mut offset: UnitOffset) -> Result<bool> {
+    loop {
+        let mut entries = unit.entries_at_offset(offset)?;
+        let die = match entries.next_dfs()? {
+            Some((_, die)) => die,
+            None => return Ok(false),
+        };
+        match die.tag() {
+            gimli::DW_TAG_const_type => return Ok(true),
+            gimli::DW_TAG_volatile_type | gimli::DW_TAG_restrict_type => {
+                match die.attr_value(gimli::DW_AT_type)? {
+                    Some(AttributeValue::UnitRef(next)) => offset = next,
+                    _ => return Ok(false),
+                }
+            }
+            _ => return Ok(false),
+        }
+    }
+}
+
+/// Returns whether the name of a Rust pointer type is the name of a function pointer, like
+/// `unsafe extern "C" fn(*mut c_void)`.
+fn is_fn_pointer_name(name: &str) -> bool {
+    let name = name.trim_start_matches("unsafe ");
+    let name = if name.starts_with("extern ") {
+        name.splitn(3, ' ').nth(2).unwrap_or("")
+    } else {
+        name
+    };
+    name.starts_with("fn(")
+}
@@ -0,0 +1,47 @@
+use crate::{
+    dwarf::{type_index, Indices},
+    item::{Language, QualifiedType, Qualifier},
+};
+use anyhow::{bail, Result};
+use gimli::{DebuggingInformationEntry, Dwarf, EndianSlice, RunTimeEndian, Unit};
+use std::str;
+
+pub fn from_qualified_type(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    module: &[String],
+    language: Language,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<QualifiedType> {
+    let qualifier = match die.tag() {
+        gimli::DW_TAG_const_type => Qualifier::Const,
+        gimli::DW_TAG_volatile_type => Qualifier::Volatile,
+        gimli::DW_TAG_restrict_type => Qualifier::Restrict,
+        tag => bail!("Not a qualified type: {}", tag),
+    };
+
+    let mut name = None;
+    let mut ty = None;
+
+    let mut attrs = die.attrs();
+    while let Some(attr) = attrs.next()? {
+        match attr.name() {
+            gimli::DW_AT_name => {
+                name = Some(str::from_utf8(&dwarf.attr_string(unit, attr.value())?)?.to_string());
+            }
+            gimli::DW_AT_type => {
+                ty = Some(type_index(dwarf, unit, indices, attr.value())?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(QualifiedType {
+        name,
+        module: module.to_vec(),
+        language,
+        qualifier,
+        type_index: ty,
+    })
+}
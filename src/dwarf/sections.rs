@@ -0,0 +1,173 @@
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::ZlibDecoder;
+use object::ElfFile;
+use std::{borrow::Cow, convert::TryInto, io::Read};
+
+const SHN_XINDEX: u16 = 0xffff;
+const SHT_NOBITS: u32 = 8;
+const SHF_COMPRESSED: u64 = 0x800;
+const ELFCOMPRESS_ZLIB: u32 = 1;
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
+/// The sections of an ELF file. `object` parses the section headers, but doesn't expose whether a
+/// section is compressed, so the headers themselves are used to find and decompress sections.
+pub struct Sections<'a> {
+    elf: ElfFile<'a>,
+    file: &'a [u8],
+    /// The section name string table.
+    names: &'a [u8],
+}
+
+struct SectionHeader {
+    kind: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+}
+
+impl<'a> Sections<'a> {
+    pub fn parse(file: &'a [u8]) -> Result<Sections<'a>> {
+        let elf = ElfFile::parse(file).map_err(|e| anyhow!("{}", e))?;
+        let header = &elf.elf().header;
+        let headers = &elf.elf().section_headers;
+
+        // With extended section numbering, the index of the name table is in section 0.
+        let names_index = if header.e_shstrndx == SHN_XINDEX {
+            headers.first().map_or(0, |header| header.sh_link as usize)
+        } else {
+            usize::from(header.e_shstrndx)
+        };
+        let names = match headers.get(names_index) {
+            Some(header) => bytes(file, header.sh_offset, header.sh_size)?,
+            None => &[],
+        };
+        Ok(Sections { elf, file, names })
+    }
+
+    /// Gets the contents of the section with the given name, decompressing it if it was compressed
+    /// with `--compress-debug-sections`. For a `.debug_*` section, the legacy `.zdebug_*` section
+    /// is used if it's missing.
+    pub fn data(&self, name: &str) -> Result<Option<Cow<'a, [u8]>>> {
+        if let Some(header) = self.header(name.as_bytes()) {
+            if header.kind == SHT_NOBITS {
+                return Ok(None);
+            }
+            let data = bytes(self.file, header.offset, header.size)?;
+            if header.flags & SHF_COMPRESSED == 0 {
+                return Ok(Some(Cow::Borrowed(data)));
+            }
+            return self
+                .decompress(data)
+                .with_context(|| format!("Failed to decompress section {}", name))
+                .map(|data| Some(Cow::Owned(data)));
+        }
+
+        if let Some(rest) = name.strip_prefix(".debug_") {
+            let zname = format!(".zdebug_{}", rest);
+            if let Some(header) = self.header(zname.as_bytes()) {
+                let data = bytes(self.file, header.offset, header.size)?;
+                return decompress_gnu(data)
+                    .with_context(|| format!("Failed to decompress section {}", zname))
+                    .map(|data| Some(Cow::Owned(data)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn header(&self, name: &[u8]) -> Option<SectionHeader> {
+        self.elf
+            .elf()
+            .section_headers
+            .iter()
+            .find(|header| {
+                let name_at = self.names.get(header.sh_name..).unwrap_or(&[]);
+                let end = name_at
+                    .iter()
+                    .position(|&b| b == 0)
+                    .unwrap_or(name_at.len());
+                &name_at[..end] == name
+            })
+            .map(|header| SectionHeader {
+                kind: header.sh_type,
+                flags: header.sh_flags,
+                offset: header.sh_offset,
+                size: header.sh_size,
+            })
+    }
+
+    /// Decompresses a section that starts with an `Elf32_Chdr` or `Elf64_Chdr`.
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let little_endian = self.elf.elf().little_endian;
+        let (kind, size, start) = if self.elf.elf().is_64 {
+            (
+                read_u32(data, 0, little_endian)?,
+                read_u64(data, 8, little_endian)?,
+                24,
+            )
+        } else {
+            (
+                read_u32(data, 0, little_endian)?,
+                read_u32(data, 4, little_endian)?.into(),
+                12,
+            )
+        };
+        let payload = &data[start.min(data.len())..];
+        match kind {
+            ELFCOMPRESS_ZLIB => decompress_to(ZlibDecoder::new(payload), payload.len(), size),
+            ELFCOMPRESS_ZSTD => {
+                decompress_to(zstd::stream::Decoder::new(payload)?, payload.len(), size)
+            }
+            kind => bail!("Unknown compression type: {}", kind),
+        }
+    }
+}
+
+/// Decompresses a legacy `.zdebug_*` section, which starts with `ZLIB` and the big-endian size of
+/// the decompressed data.
+fn decompress_gnu(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 12 || &data[..4] != b"ZLIB" {
+        bail!("Unknown compression format");
+    }
+    let size = u64::from_be_bytes(data[4..12].try_into().unwrap());
+    decompress_to(ZlibDecoder::new(&data[12..]), data.len() - 12, size)
+}
+
+/// Reads all of a decompressor's output, which must be `size` bytes. The size comes from the file,
+/// so it only bounds the output, and isn't trusted for the initial allocation.
+fn decompress_to(decoder: impl Read, compressed_size: usize, size: u64) -> Result<Vec<u8>> {
+    let capacity = size.min(compressed_size.saturating_mul(4) as u64);
+    let mut out = Vec::with_capacity(capacity as usize);
+    decoder.take(size.saturating_add(1)).read_to_end(&mut out)?;
+    if out.len() as u64 != size {
+        bail!(
+            "Decompressed to more or fewer than the expected {} bytes",
+            size
+        );
+    }
+    Ok(out)
+}
+
+fn bytes(file: &[u8], offset: u64, len: u64) -> Result<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| file.get(offset as usize..end as usize))
+        .ok_or_else(|| anyhow!("Truncated ELF file"))
+}
+
+fn read_u32(data: &[u8], offset: usize, little_endian: bool) -> Result<u32> {
+    let bytes = bytes(data, offset as u64, 4)?.try_into().unwrap();
+    Ok(if little_endian {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+fn read_u64(data: &[u8], offset: usize, little_endian: bool) -> Result<u64> {
+    let bytes = bytes(data, offset as u64, 8)?.try_into().unwrap();
+    Ok(if little_endian {
+        u64::from_le_bytes(bytes)
+    } else {
+        u64::from_be_bytes(bytes)
+    })
+}
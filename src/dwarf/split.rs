@@ -0,0 +1,308 @@
+use crate::dwarf::sections::Sections;
+use anyhow::{anyhow, bail, Result};
+use gimli::{
+    AttributeValue, DebugAddrBase, DebuggingInformationEntry, Dwarf, DwoId, EndianSlice, Reader,
+    RunTimeEndian, Unit, UnitType,
+};
+use log::warn;
+use std::{
+    borrow::Cow, collections::HashMap, ffi::OsStr, fs, os::unix::ffi::OsStrExt, path::PathBuf,
+};
+
+/// The sections a split unit can use, besides `.debug_addr`, which stays in the main file.
+const DWO_SECTIONS: &[&str] = &[
+    ".debug_abbrev",
+    ".debug_info",
+    ".debug_line",
+    ".debug_loc",
+    ".debug_loclists",
+    ".debug_macinfo",
+    ".debug_macro",
+    ".debug_rnglists",
+    ".debug_str",
+    ".debug_str_offsets",
+    ".debug_types",
+];
+
+/// A unit in the main file that only points to the split unit with the actual debug info, which
+/// is in a `.dwo` file or a `.dwp` package.
+pub struct Skeleton {
+    dwo_name: PathBuf,
+    comp_dir: Option<PathBuf>,
+    dwo_id: Option<u64>,
+    addr_base: DebugAddrBase,
+}
+
+/// The sections of a `.dwo` file, or of one unit's contributions to a `.dwp` package.
+pub struct SplitFile<'a> {
+    sections: HashMap<&'static str, Cow<'a, [u8]>>,
+    /// The base of the skeleton unit's addresses in `.debug_addr`, which the split units share.
+    addr_base: Option<DebugAddrBase>,
+}
+
+impl<'a> SplitFile<'a> {
+    /// Gets the contents of the section with the given name, without the `.dwo` suffix.
+    pub fn section<'b>(&'b self, name: &str, debug_addr: &'b [u8]) -> Cow<'b, [u8]> {
+        if name == ".debug_addr" {
+            return Cow::Borrowed(debug_addr);
+        }
+        match self.sections.get(name) {
+            Some(data) => Cow::Borrowed(&data[..]),
+            None => Cow::Borrowed(&[][..]),
+        }
+    }
+
+    /// Gives a split unit the base of its skeleton's addresses in `.debug_addr`.
+    pub fn fix_unit(&self, unit: &mut Unit<EndianSlice<RunTimeEndian>>) {
+        if let Some(addr_base) = self.addr_base {
+            unit.addr_base = addr_base;
+        }
+    }
+}
+
+/// Returns whether the root DIE of a unit is a skeleton unit.
+pub fn is_skeleton(die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>) -> Result<bool> {
+    Ok(die.tag() == gimli::DW_TAG_skeleton_unit
+        || die.attr_value(gimli::DW_AT_dwo_name)?.is_some()
+        || die.attr_value(gimli::DW_AT_GNU_dwo_name)?.is_some())
+}
+
+/// Finds the skeleton units in a file.
+pub fn find_skeletons(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    units: &[Unit<EndianSlice<RunTimeEndian>>],
+) -> Result<Vec<Skeleton>> {
+    let mut skeletons = Vec::new();
+    for unit in units {
+        let mut tree = unit.entries_tree(None)?;
+        let root = tree.root()?;
+        let die = root.entry();
+
+        let mut dwo_name = None;
+        let mut dwo_id = None;
+        let mut addr_base = unit.addr_base;
+        let mut attrs = die.attrs();
+        while let Some(attr) = attrs.next()? {
+            match attr.name() {
+                gimli::DW_AT_dwo_name | gimli::DW_AT_GNU_dwo_name => {
+                    let name = dwarf.attr_string(unit, attr.value())?;
+                    dwo_name = Some(PathBuf::from(OsStr::from_bytes(&name)));
+                }
+                gimli::DW_AT_GNU_dwo_id => {
+                    dwo_id = match attr.value() {
+                        AttributeValue::DwoId(DwoId(id)) => Some(id),
+                        value => value.udata_value(),
+                    };
+                }
+                gimli::DW_AT_GNU_addr_base => {
+                    if let Some(base) = attr.value().udata_value() {
+                        addr_base = DebugAddrBase(base as usize);
+                    } else if let AttributeValue::DebugAddrBase(base) = attr.value() {
+                        addr_base = base;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let dwo_name = match dwo_name {
+            Some(dwo_name) => dwo_name,
+            None => continue,
+        };
+        // DWARF 5 moved the ID into the unit header.
+        if let UnitType::Skeleton(DwoId(id)) = unit.header.type_() {
+            dwo_id = Some(id);
+        }
+        skeletons.push(Skeleton {
+            dwo_name,
+            comp_dir: unit
+                .comp_dir
+                .map(|comp_dir| PathBuf::from(OsStr::from_bytes(&comp_dir))),
+            dwo_id,
+            addr_base,
+        });
+    }
+    Ok(skeletons)
+}
+
+/// Reads the `.dwo` files named by skeleton units, looking for them in their compilation
+/// directory and then in the given directories. Files that can't be found are skipped with a
+/// warning.
+pub fn read_dwo_files(
+    skeletons: &[Skeleton],
+    dwo_dirs: &[PathBuf],
+) -> Result<Vec<(Vec<u8>, DebugAddrBase)>> {
+    let mut files = Vec::new();
+    for skeleton in skeletons {
+        let mut candidates = vec![match &skeleton.comp_dir {
+            Some(comp_dir) => comp_dir.join(&skeleton.dwo_name),
+            None => skeleton.dwo_name.clone(),
+        }];
+        for dir in dwo_dirs {
+            candidates.push(dir.join(&skeleton.dwo_name));
+            if let Some(name) = skeleton.dwo_name.file_name() {
+                candidates.push(dir.join(name));
+            }
+        }
+
+        match candidates.iter().find(|candidate| candidate.is_file()) {
+            Some(path) => files.push((fs::read(path)?, skeleton.addr_base)),
+            None => warn!(
+                "Couldn't find the split debug info {}",
+                skeleton.dwo_name.display()
+            ),
+        }
+    }
+    Ok(files)
+}
+
+/// Gets the sections of a `.dwo` file.
+pub fn from_dwo<'a>(sections: &Sections<'a>, addr_base: DebugAddrBase) -> Result<SplitFile<'a>> {
+    let mut file = SplitFile {
+        sections: HashMap::new(),
+        addr_base: Some(addr_base),
+    };
+    for &name in DWO_SECTIONS {
+        if let Some(data) = sections.data(&format!("{}.dwo", name))? {
+            file.sections.insert(name, data);
+        }
+    }
+    Ok(file)
+}
+
+/// Splits a `.dwp` package into the contributions of each of its units, using the
+/// `.debug_cu_index` and `.debug_tu_index` sections.
+pub fn from_dwp<'a>(
+    sections: &Sections<'a>,
+    endian: RunTimeEndian,
+    skeletons: &[Skeleton],
+) -> Result<Vec<SplitFile<'a>>> {
+    let mut package = HashMap::new();
+    for &name in DWO_SECTIONS {
+        if let Some(data) = sections.data(&format!("{}.dwo", name))? {
+            package.insert(name, data);
+        }
+    }
+
+    let mut files = Vec::new();
+    for &index_name in &[".debug_cu_index", ".debug_tu_index"] {
+        let index = match sections.data(index_name)? {
+            Some(index) => index,
+            None => continue,
+        };
+        for (signature, contributions) in read_index(&index, endian)? {
+            let mut file = SplitFile {
+                sections: HashMap::new(),
+                addr_base: None,
+            };
+            if index_name == ".debug_cu_index" {
+                file.addr_base = skeletons
+                    .iter()
+                    .find(|skeleton| skeleton.dwo_id == Some(signature))
+                    .map(|skeleton| skeleton.addr_base);
+            }
+            // Strings aren't split up by unit.
+            if let Some(data) = package.get(".debug_str") {
+                file.sections.insert(".debug_str", data.clone());
+            }
+            for (name, offset, size) in contributions {
+                let data = package
+                    .get(name)
+                    .ok_or_else(|| anyhow!("Missing {}.dwo section", name))?;
+                let range = offset..offset + size;
+                let data = match data {
+                    Cow::Borrowed(data) => data.get(range).map(Cow::Borrowed),
+                    Cow::Owned(data) => data.get(range).map(|data| Cow::Owned(data.to_vec())),
+                }
+                .ok_or_else(|| anyhow!("Invalid contribution to {}.dwo", name))?;
+                file.sections.insert(name, data);
+            }
+            files.push(file);
+        }
+    }
+    Ok(files)
+}
+
+/// The name, offset, and size of each of a unit's contributions to the sections of a package.
+type Contributions = Vec<(&'static str, usize, usize)>;
+
+/// Reads a `.debug_cu_index` or `.debug_tu_index` section, giving the signature of each unit and
+/// its contributions.
+fn read_index(index: &[u8], endian: RunTimeEndian) -> Result<Vec<(u64, Contributions)>> {
+    let mut input = EndianSlice::new(index, endian);
+    // Version 2 has a 4-byte version, and version 5 a 2-byte version followed by padding.
+    let (first, second) = (input.read_u16()?, input.read_u16()?);
+    let version = if first != 0 { first } else { second };
+    let section_count = input.read_u32()? as usize;
+    let unit_count = input.read_u32()? as usize;
+    let slot_count = input.read_u32()? as usize;
+
+    // The counts come from the file, so check that the tables fit before allocating anything.
+    let tables_size = unit_count
+        .checked_mul(2)
+        .and_then(|rows| rows.checked_add(1))
+        .and_then(|rows| rows.checked_mul(section_count))
+        .and_then(|cells| cells.checked_mul(4))
+        .and_then(|size| size.checked_add(slot_count.checked_mul(12)?));
+    match tables_size {
+        Some(size) if size <= input.len() => {}
+        _ => bail!("Index tables don't fit in the section"),
+    }
+
+    let mut signatures = Vec::with_capacity(slot_count);
+    for _ in 0..slot_count {
+        signatures.push(input.read_u64()?);
+    }
+    let mut rows = Vec::with_capacity(slot_count);
+    for _ in 0..slot_count {
+        rows.push(input.read_u32()? as usize);
+    }
+    let mut names = Vec::with_capacity(section_count);
+    for _ in 0..section_count {
+        names.push(dwp_section_name(version, input.read_u32()?));
+    }
+    let mut offsets = Vec::with_capacity(unit_count * section_count);
+    for _ in 0..unit_count * section_count {
+        offsets.push(input.read_u32()? as usize);
+    }
+    let mut sizes = Vec::with_capacity(unit_count * section_count);
+    for _ in 0..unit_count * section_count {
+        sizes.push(input.read_u32()? as usize);
+    }
+
+    let mut units = Vec::new();
+    for (signature, row) in signatures.into_iter().zip(rows) {
+        // Rows are numbered from one, so zero marks an empty slot.
+        if row == 0 || row > unit_count {
+            continue;
+        }
+        let contributions = names
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| {
+                let cell = (row - 1) * section_count + i;
+                name.map(|name| (name, offsets[cell], sizes[cell]))
+            })
+            .collect();
+        units.push((signature, contributions));
+    }
+    Ok(units)
+}
+
+/// Gets the name of a section in a `.dwp` package, without the `.dwo` suffix, from its
+/// `DW_SECT_*` ID, which differ between versions of the index.
+fn dwp_section_name(version: u16, id: u32) -> Option<&'static str> {
+    match (version, id) {
+        (_, 1) => Some(".debug_info"),
+        (2, 2) => Some(".debug_types"),
+        (_, 3) => Some(".debug_abbrev"),
+        (_, 4) => Some(".debug_line"),
+        (2, 5) => Some(".debug_loc"),
+        (_, 5) => Some(".debug_loclists"),
+        (_, 6) => Some(".debug_str_offsets"),
+        (2, 7) => Some(".debug_macinfo"),
+        (_, 7) | (2, 8) => Some(".debug_macro"),
+        (_, 8) => Some(".debug_rnglists"),
+        _ => None,
+    }
+}
@@ -1,19 +1,46 @@
 use crate::{
-    dwarf::{dump_die, handle_node},
-    item::{Item, Structure, StructureMember},
+    dwarf::{
+        dump_die, global_offset, handle_node, member_offset, template_parameter, type_index,
+        Indices,
+    },
+    item::{Item, Language, Opaque, Repr, Slice, Structure, StructureMember, TraitObject, Tuple},
 };
 use anyhow::{anyhow, bail, Result};
 use gimli::{
-    AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, EntriesTreeNode, RunTimeEndian,
-    Unit, UnitOffset,
+    AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, Endianity, EntriesTreeNode,
+    Reader, RunTimeEndian, Section, Unit,
 };
 use log::debug;
 use std::str;
 
+/// Returns whether a structure or union is only declared, so its layout is unknown.
+pub fn is_declaration(die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>) -> Result<bool> {
+    Ok(die.attr_value(gimli::DW_AT_declaration)? == Some(AttributeValue::Flag(true)))
+}
+
+pub fn from_declaration(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    module: &[String],
+    language: Language,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<Opaque> {
+    let name = die
+        .attr_value(gimli::DW_AT_name)?
+        .ok_or_else(|| anyhow!("Missing DW_AT_name"))?;
+    Ok(Opaque {
+        name: str::from_utf8(&dwarf.attr_string(unit, name)?)?.to_string(),
+        module: module.to_vec(),
+        language,
+    })
+}
+
 pub fn from_structure_type(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
     module: &[String],
+    language: Language,
     die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
 ) -> Result<Structure> {
     let mut name = None;
@@ -36,18 +63,143 @@ pub fn from_structure_type(
         }
     }
 
+    // C and C++ allow anonymous structures and unions, which are named after their index here.
+    let name = match name {
+        Some(name) => name,
+        None if language != Language::Rust => format!(
+            "{{anonymous@0x{:x}}}",
+            global_offset(dwarf, unit, indices, die.offset())?
+        ),
+        None => bail!("Missing DW_AT_name"),
+    };
+
     Ok(Structure {
-        name: name.ok_or_else(|| anyhow!("Missing DW_AT_name"))?,
+        name,
         module: module.to_vec(),
+        language,
         size: size.ok_or_else(|| anyhow!("Missing or invalid DW_AT_byte_size"))?,
-        alignment: alignment.ok_or_else(|| anyhow!("Missing or invalid DW_AT_alignment"))?,
+        alignment: alignment.unwrap_or(0),
+        alignment_inferred: alignment.is_none(),
         members: Vec::new(),
+        template_parameters: Vec::new(),
+        // Filled in by layout::classify_structures.
+        repr: Repr::Rust,
     })
 }
 
+/// Converts a structure to an item, recognizing zero-sized types, and the fat pointers, tuples, and
+/// closures that rustc emits as structures.
+pub fn into_item(mut structure: Structure) -> Item {
+    if is_closure_name(&structure.name) {
+        return Item::Closure(structure);
+    }
+    if structure.size == 0 {
+        return Item::ZeroSized(structure);
+    }
+    if structure.name.starts_with('(')
+        && structure
+            .members
+            .iter()
+            .all(|member| tuple_position(&member.name).is_some())
+    {
+        let mut elements = structure.members;
+        elements.sort_by_key(|member| tuple_position(&member.name));
+        return Item::Tuple(Tuple {
+            name: structure.name,
+            module: structure.module,
+            language: structure.language,
+            size: structure.size,
+            alignment: structure.alignment,
+            alignment_inferred: structure.alignment_inferred,
+            elements,
+        });
+    }
+
+    // Only Rust's own pointer types are fat pointers, even if another structure has the same
+    // members.
+    if structure.language != Language::Rust || !is_fat_pointer_name(&structure.name) {
+        return Item::Structure(structure);
+    }
+    let mutable = structure.name.starts_with("&mut ")
+        || structure.name.starts_with("*mut ")
+        || structure.name.starts_with("alloc::boxed::Box<");
+
+    let member_names = structure
+        .members
+        .iter()
+        .map(|member| member.name.as_str())
+        .collect::<Vec<_>>();
+    match member_names.as_slice() {
+        ["data_ptr", "length"] => {
+            let length = structure.members.pop().unwrap();
+            let data_ptr = structure.members.pop().unwrap();
+            let is_str = matches!(
+                structure.name.as_str(),
+                "&str" | "&mut str" | "*const str" | "*mut str"
+            ) || matches!(
+                structure.name.strip_prefix("alloc::boxed::Box<str"),
+                Some(rest) if rest == ">" || rest.starts_with(", ")
+            );
+            let slice = Slice {
+                name: structure.name,
+                module: structure.module,
+                language: structure.language,
+                size: structure.size,
+                alignment: structure.alignment,
+                alignment_inferred: structure.alignment_inferred,
+                mutable,
+                data_ptr,
+                length,
+            };
+            if is_str {
+                Item::Str(slice)
+            } else {
+                Item::Slice(slice)
+            }
+        }
+        ["pointer", "vtable"] => {
+            let vtable = structure.members.pop().unwrap();
+            let pointer = structure.members.pop().unwrap();
+            Item::TraitObject(TraitObject {
+                name: structure.name,
+                module: structure.module,
+                language: structure.language,
+                size: structure.size,
+                alignment: structure.alignment,
+                alignment_inferred: structure.alignment_inferred,
+                mutable,
+                pointer,
+                vtable,
+            })
+        }
+        _ => Item::Structure(structure),
+    }
+}
+
+/// Returns whether the name of a structure is that of a reference, raw pointer, or `Box`, which
+/// rustc emits as structures when they point to unsized types.
+fn is_fat_pointer_name(name: &str) -> bool {
+    name.starts_with('&')
+        || name.starts_with("*const ")
+        || name.starts_with("*mut ")
+        || name.starts_with("alloc::boxed::Box<")
+}
+
+/// Returns whether the name of a structure is the name rustc gives to a closure's environment, like
+/// `{closure#0}` or `{closure_env#0}<T>`.
+fn is_closure_name(name: &str) -> bool {
+    name.starts_with("{closure") || name.starts_with("closure-")
+}
+
+/// Returns the position of a tuple element, given the name of its member, like `__0`.
+fn tuple_position(name: &str) -> Option<usize> {
+    name.strip_prefix("__")?.parse().ok()
+}
+
 pub fn modify(
     dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
     unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
     module: &mut Vec<String>,
     items: &mut Vec<(usize, Item)>,
     structure: &mut Structure,
@@ -63,6 +215,10 @@ pub fn modify(
             let mut ty = None;
             let mut offset = None;
             let mut alignment = None;
+            let mut byte_size = None;
+            let mut bit_size = None;
+            let mut bit_offset = None;
+            let mut data_bit_offset = None;
 
             let mut attrs = die.attrs();
             while let Some(attr) = attrs.next()? {
@@ -71,33 +227,135 @@ pub fn modify(
                         name = Some(string(attr.value())?);
                     }
                     gimli::DW_AT_type => {
-                        ty = Some(match attr.value() {
-                            AttributeValue::UnitRef(UnitOffset(n)) => n,
-                            val => bail!("Unexpected DW_AT_type value: {:?}", val),
-                        });
+                        ty = Some(type_index(dwarf, unit, indices, attr.value())?);
                     }
                     gimli::DW_AT_data_member_location => {
-                        offset = attr.value().udata_value();
+                        offset = Some(member_offset(unit, attr.value())?);
                     }
                     gimli::DW_AT_alignment => {
                         alignment = attr.value().udata_value();
                     }
+                    gimli::DW_AT_byte_size => {
+                        byte_size = attr.value().udata_value();
+                    }
+                    gimli::DW_AT_bit_size => {
+                        bit_size = attr.value().udata_value();
+                    }
+                    gimli::DW_AT_bit_offset => {
+                        bit_offset = attr.value().udata_value();
+                    }
+                    gimli::DW_AT_data_bit_offset => {
+                        data_bit_offset = attr.value().udata_value();
+                    }
+                    // Static data members of C++ classes before DWARF 5.
+                    gimli::DW_AT_declaration => return Ok(()),
+                    _ => {}
+                }
+            }
+
+            let bit_offset = match (bit_size, data_bit_offset, bit_offset) {
+                (None, _, _) => None,
+                (Some(_), Some(data_bit_offset), _) => Some(data_bit_offset),
+                // DWARF 2 and 3 count the offset from the most significant bit of a storage unit
+                // of DW_AT_byte_size bytes.
+                (Some(bit_size), None, Some(bit_offset)) => {
+                    let byte_size = byte_size
+                        .ok_or_else(|| anyhow!("Missing DW_AT_byte_size from bitfield"))?;
+                    let base = offset.unwrap_or(0) * 8;
+                    if dwarf.debug_info.reader().endian().is_little_endian() {
+                        let from_lsb = (byte_size * 8)
+                            .checked_sub(bit_offset)
+                            .and_then(|bits| bits.checked_sub(bit_size));
+                        match from_lsb {
+                            Some(from_lsb) => Some(base + from_lsb),
+                            None => {
+                                let _ = dump_die(dwarf, unit, die, 0, "<bf> ");
+                                bail!(
+                                    "Bitfield {:?} in {} at 0x{:x} doesn't fit in its storage unit",
+                                    name,
+                                    structure.name,
+                                    die.offset().0
+                                )
+                            }
+                        }
+                    } else {
+                        Some(base + bit_offset)
+                    }
+                }
+                (Some(_), None, None) => Some(offset.unwrap_or(0) * 8),
+            };
+            let offset = match bit_offset {
+                Some(bit_offset) => bit_offset / 8,
+                None => offset.ok_or_else(|| anyhow!("Missing DW_AT_data_member_location"))?,
+            };
+
+            // Members of anonymous structures and unions in C and C++ are unnamed.
+            let name = match name {
+                Some(name) => name,
+                None if structure.language != Language::Rust => {
+                    format!("_anonymous{}", structure.members.len())
+                }
+                None => bail!("Missing DW_AT_name"),
+            };
+
+            structure.members.push(StructureMember {
+                name,
+                type_index: ty.ok_or_else(|| anyhow!("Missing DW_AT_type"))?,
+                offset,
+                // Filled in by layout::infer_alignments if missing.
+                alignment: alignment.unwrap_or(0),
+                bit_offset,
+                bit_size,
+            });
+        }
+        gimli::DW_TAG_inheritance => {
+            let mut ty = None;
+            let mut offset = None;
+
+            let mut attrs = die.attrs();
+            while let Some(attr) = attrs.next()? {
+                match attr.name() {
+                    gimli::DW_AT_type => {
+                        ty = Some(type_index(dwarf, unit, indices, attr.value())?);
+                    }
+                    gimli::DW_AT_data_member_location => {
+                        offset = Some(member_offset(unit, attr.value())?);
+                    }
                     _ => {}
                 }
             }
 
+            // Base classes are laid out like members.
             structure.members.push(StructureMember {
-                name: name.ok_or_else(|| anyhow!("Missing DW_AT_name"))?,
+                name: format!("_base{}", structure.members.len()),
                 type_index: ty.ok_or_else(|| anyhow!("Missing DW_AT_type"))?,
-                offset: offset
-                    .ok_or_else(|| anyhow!("Missing or invalid DW_AT_data_member_location"))?,
-                alignment: alignment
-                    .ok_or_else(|| anyhow!("Missing or invalid DW_AT_alignment"))?,
+                offset: offset.unwrap_or(0),
+                alignment: 0,
+                bit_offset: None,
+                bit_size: None,
             });
         }
-        gimli::DW_TAG_subprogram => {
+        gimli::DW_TAG_template_type_parameter => {
+            let param =
+                template_parameter::from_template_type_parameter(dwarf, unit, indices, die)?;
+            structure.template_parameters.push(param);
+        }
+        gimli::DW_TAG_subprogram
+        | gimli::DW_TAG_array_type
+        | gimli::DW_TAG_structure_type
+        | gimli::DW_TAG_class_type
+        | gimli::DW_TAG_typedef
+        | gimli::DW_TAG_union_type => {
             module.push(structure.name.clone());
-            handle_node(dwarf, unit, module, items, node)?;
+            handle_node(
+                dwarf,
+                unit,
+                indices,
+                structure.language,
+                module,
+                items,
+                node,
+            )?;
             module.pop();
         }
         tag => {
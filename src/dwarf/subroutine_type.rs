@@ -0,0 +1,82 @@
+use crate::{
+    dwarf::{dump_die, type_index, Indices},
+    item::{CallingConvention, FunctionPointer, Language},
+};
+use anyhow::{anyhow, bail, Result};
+use gimli::{
+    AttributeValue, DebuggingInformationEntry, DwCc, Dwarf, EndianSlice, RunTimeEndian, Unit,
+};
+use log::debug;
+use std::str;
+
+pub fn from_subroutine_type(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    module: &[String],
+    language: Language,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<FunctionPointer> {
+    let mut name = None;
+    let mut ret_type_index = None;
+    let mut calling_convention = CallingConvention::C;
+
+    let mut attrs = die.attrs();
+    while let Some(attr) = attrs.next()? {
+        match attr.name() {
+            gimli::DW_AT_name => {
+                name = Some(str::from_utf8(&dwarf.attr_string(unit, attr.value())?)?.to_string());
+            }
+            gimli::DW_AT_type => {
+                ret_type_index = Some(type_index(dwarf, unit, indices, attr.value())?);
+            }
+            gimli::DW_AT_calling_convention => {
+                calling_convention = match attr.value() {
+                    AttributeValue::CallingConvention(gimli::DW_CC_normal) => CallingConvention::C,
+                    // LLVM reuses Borland's value for stdcall.
+                    AttributeValue::CallingConvention(DwCc(0xb1)) => CallingConvention::StdCall,
+                    AttributeValue::CallingConvention(DwCc(n)) => CallingConvention::Other(n),
+                    val => bail!("Invalid DW_AT_calling_convention: {:?}", val),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Ok(FunctionPointer {
+        name,
+        module: module.to_vec(),
+        language,
+        ret_type_index,
+        arguments: Vec::new(),
+        variadic: false,
+        calling_convention,
+    })
+}
+
+pub fn modify(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    function_pointer: &mut FunctionPointer,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<()> {
+    match die.tag() {
+        gimli::DW_TAG_formal_parameter => {
+            let ty = die
+                .attr_value(gimli::DW_AT_type)?
+                .ok_or_else(|| anyhow!("Missing DW_AT_type from DW_TAG_formal_parameter"))?;
+            let ty = type_index(dwarf, unit, indices, ty)?;
+            function_pointer.arguments.push(ty);
+        }
+        gimli::DW_TAG_unspecified_parameters => {
+            function_pointer.variadic = true;
+        }
+        tag => {
+            debug!("In function pointer: {:?}", function_pointer.name);
+            debug!("Unsupported tag: {}", tag);
+            dump_die(dwarf, unit, die, 0, "<mp> ")?
+        }
+    }
+    Ok(())
+}
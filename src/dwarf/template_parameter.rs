@@ -0,0 +1,35 @@
+use crate::{
+    dwarf::{type_index, Indices},
+    item::TemplateParameter,
+};
+use anyhow::{anyhow, Result};
+use gimli::{DebuggingInformationEntry, Dwarf, EndianSlice, RunTimeEndian, Unit};
+use std::str;
+
+pub fn from_template_type_parameter(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<TemplateParameter> {
+    let mut name = None;
+    let mut ty = None;
+
+    let mut attrs = die.attrs();
+    while let Some(attr) = attrs.next()? {
+        match attr.name() {
+            gimli::DW_AT_name => {
+                name = Some(str::from_utf8(&dwarf.attr_string(unit, attr.value())?)?.to_string());
+            }
+            gimli::DW_AT_type => {
+                ty = Some(type_index(dwarf, unit, indices, attr.value())?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(TemplateParameter {
+        name: name.ok_or_else(|| anyhow!("Missing DW_AT_name"))?,
+        type_index: ty,
+    })
+}
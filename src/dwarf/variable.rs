@@ -0,0 +1,144 @@
+use crate::{
+    dwarf::{full_name, is_library_name, type_index, Indices},
+    item::{Language, Static},
+};
+use anyhow::{anyhow, Result};
+use gimli::{
+    AttributeValue, DebuggingInformationEntry, Dwarf, EndianSlice, Expression, Operation,
+    RunTimeEndian, Unit,
+};
+use object::{ElfFile, Object, ObjectSection, SectionKind, SymbolKind};
+use std::{collections::HashSet, str};
+
+pub fn from_variable(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    indices: &Indices,
+    module: &[String],
+    language: Language,
+    die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+) -> Result<Option<Static>> {
+    let string =
+        |val| -> Result<_> { Ok(str::from_utf8(&dwarf.attr_string(unit, val)?)?.to_string()) };
+
+    let mut name = None;
+    let mut linkage_name = None;
+    let mut ty = None;
+    let mut address = None;
+    let mut external = false;
+
+    let mut attrs = die.attrs();
+    while let Some(attr) = attrs.next()? {
+        match attr.name() {
+            gimli::DW_AT_name => {
+                name = Some(string(attr.value())?);
+            }
+            gimli::DW_AT_external => match attr.value() {
+                AttributeValue::Flag(true) => external = true,
+                _ => return Ok(None),
+            },
+            gimli::DW_AT_declaration => return Ok(None),
+            gimli::DW_AT_type => {
+                ty = Some(type_index(dwarf, unit, indices, attr.value())?);
+            }
+            gimli::DW_AT_linkage_name => {
+                linkage_name = Some(string(attr.value())?);
+            }
+            gimli::DW_AT_location => {
+                if let AttributeValue::Exprloc(expr) = attr.value() {
+                    address = location_address(dwarf, unit, expr)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // C and C++ mark every externally visible variable, so the rest are static.
+    if !external && language != Language::Rust {
+        return Ok(None);
+    }
+
+    let name = name.ok_or_else(|| anyhow!("Missing DW_AT_name"))?;
+    if name.ends_with("{vtable}") {
+        return Ok(None);
+    }
+
+    // #[no_mangle] statics have no linkage name, since it would be the same as their name.
+    let full_name = full_name(
+        language,
+        module,
+        Some(&name),
+        linkage_name.as_ref().unwrap_or(&name),
+    );
+    if is_library_name(&full_name) {
+        return Ok(None);
+    }
+
+    Ok(Some(Static {
+        name,
+        linkage_name,
+        full_name,
+        module: module.to_vec(),
+        language,
+        type_index: ty.ok_or_else(|| anyhow!("Missing DW_AT_type"))?,
+        mutable: true,
+        address,
+    }))
+}
+
+/// Gets the address of a static from its location expression, if the expression is just an
+/// address. Other expressions, like those of thread-local statics, don't give a fixed address.
+fn location_address(
+    dwarf: &Dwarf<EndianSlice<RunTimeEndian>>,
+    unit: &Unit<EndianSlice<RunTimeEndian>>,
+    expr: Expression<EndianSlice<RunTimeEndian>>,
+) -> Result<Option<u64>> {
+    let mut bytes = expr.0;
+    let mut ops = Vec::new();
+    while !bytes.is_empty() {
+        // This also parses DW_OP_GNU_addr_index and DW_OP_GNU_const_index, the split DWARF
+        // extensions that became DW_OP_addrx and DW_OP_constx in DWARF 5.
+        ops.push(Operation::parse(&mut bytes, unit.encoding())?);
+    }
+
+    let address = match ops.as_slice() {
+        [Operation::Address { address }] => Some(*address),
+        // DWARF 5 and split DWARF put addresses in .debug_addr, so they're relocated only once.
+        [Operation::AddressIndex { index }] | [Operation::ConstantIndex { index }] => Some(
+            dwarf
+                .debug_addr
+                .get_address(unit.encoding().address_size, unit.addr_base, *index)?,
+        ),
+        _ => None,
+    };
+    // The linker leaves the addresses of statics it discarded as 0.
+    Ok(address.filter(|&address| address != 0))
+}
+
+/// Gets the names of the variables exported from the dynamic symbol table of a file.
+pub fn exported_symbols(elf: &ElfFile) -> HashSet<String> {
+    elf.dynamic_symbols()
+        .filter(|(_, symbol)| {
+            matches!(symbol.kind(), SymbolKind::Data | SymbolKind::Tls)
+                && !symbol.is_undefined()
+                && !symbol.is_local()
+        })
+        .filter_map(|(_, symbol)| Some(symbol.name()?.to_string()))
+        .collect()
+}
+
+/// Returns whether the static at the given address is in a writable section. Statics whose
+/// address is unknown are assumed to be writable.
+pub fn is_writable(elf: &ElfFile, address: u64) -> bool {
+    elf.sections()
+        .find(|section| {
+            section.address() <= address && address < section.address() + section.size()
+        })
+        .map(|section| match section.kind() {
+            SectionKind::ReadOnlyData | SectionKind::ReadOnlyString | SectionKind::Text => false,
+            // Statics containing pointers live here, and are only written by the dynamic linker.
+            _ if section.name() == Some(".data.rel.ro") => false,
+            _ => true,
+        })
+        .unwrap_or(true)
+}
@@ -10,11 +10,54 @@ pub enum Item {
     /// A built-in type.
     BaseType(BaseType),
 
-    /// A pointer type.
+    /// A pointer or reference type.
     PointerType(PointerType),
 
     /// A structure.
     Structure(Structure),
+
+    /// An enum whose variants may have fields.
+    Enum(Enum),
+
+    /// A union.
+    Union(Union),
+
+    /// A fixed-size array.
+    Array(Array),
+
+    /// A type alias.
+    Typedef(Typedef),
+
+    /// A `const`, `volatile`, or `restrict` qualified type.
+    QualifiedType(QualifiedType),
+
+    /// The type of a function, as pointed to by a function pointer.
+    FunctionPointer(FunctionPointer),
+
+    /// A pointer to a slice, like `&[T]`.
+    Slice(Slice),
+
+    /// A pointer to a string slice, like `&str`.
+    Str(Slice),
+
+    /// A pointer to a trait object, like `&dyn Trait`.
+    TraitObject(TraitObject),
+
+    /// A global or static variable.
+    Static(Static),
+
+    /// A tuple.
+    Tuple(Tuple),
+
+    /// The environment of a closure. These are only useful to look up the types of pointers to
+    /// them, so they aren't given names in bindings.
+    Closure(Structure),
+
+    /// A structure with a size of zero, like `PhantomData<T>`. These are passed as nothing at all.
+    ZeroSized(Structure),
+
+    /// A structure or union that is declared but not defined, so only pointers to it can be used.
+    Opaque(Opaque),
 }
 
 /// A method or function.
@@ -32,11 +75,62 @@ pub struct Function {
     /// The module in which the function appeared.
     pub module: Vec<String>,
 
+    /// The language of the compilation unit the function appeared in.
+    pub language: Language,
+
     /// The index of the return type. If `None`, the function doesn't return a value.
     pub ret_type_index: Option<usize>,
 
     /// The arguments to the function, as pairs of `(name, type index)`.
     pub arguments: Vec<(Option<String>, usize)>,
+
+    /// The generic type parameters the function was instantiated with.
+    pub template_parameters: Vec<TemplateParameter>,
+
+    /// The symbol the function is exported from the `.so` as.
+    pub symbol: Option<Symbol>,
+
+    /// Whether the function was only found in the dynamic symbol table, so its return type and
+    /// arguments aren't known and `ret_type_index` and `arguments` are meaningless.
+    pub unknown_signature: bool,
+}
+
+/// An entry in the dynamic symbol table of a `.so`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Symbol {
+    /// The address of the symbol.
+    pub address: u64,
+
+    /// The size of the symbol, in bytes.
+    pub size: u64,
+
+    /// Whether the symbol can be overridden by another definition.
+    pub binding: SymbolBinding,
+}
+
+/// The binding of a symbol.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SymbolBinding {
+    Global,
+    Weak,
+}
+
+/// A generic type parameter of a function or structure, and the type it was instantiated with.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TemplateParameter {
+    /// The name of the parameter, like `T`.
+    pub name: String,
+
+    /// The index of the type the parameter was instantiated with. If `None`, this is `void`.
+    pub type_index: Option<usize>,
+}
+
+/// The source language of an item.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Language {
+    Rust,
+    C,
+    Cpp,
 }
 
 /// A built-in type.
@@ -48,6 +142,9 @@ pub struct BaseType {
     /// The module in which the type appeared.
     pub module: Vec<String>,
 
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
     /// The size of the type, in bytes.
     pub size: u64,
 
@@ -69,17 +166,42 @@ pub enum BaseTypeKind {
     Unit,
 }
 
-/// A pointer type.
+/// A pointer or reference type.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PointerType {
-    /// The name of the type.
-    pub name: String,
+    /// The name of the type, if it has one.
+    pub name: Option<String>,
 
     /// The module in which the type appeared.
     pub module: Vec<String>,
 
-    /// The index of the type being pointed to.
-    pub type_index: usize,
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The index of the type being pointed to. If `None`, this is a pointer to `void`.
+    pub type_index: Option<usize>,
+
+    /// The kind of pointer this is.
+    pub kind: PointerKind,
+
+    /// Whether the value being pointed to may be modified through the pointer.
+    pub mutable: bool,
+
+    /// Whether the pointer may be null.
+    pub nullable: bool,
+}
+
+/// The kind of pointer a `PointerType` is.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PointerKind {
+    /// A raw pointer, or a Rust reference emitted as a pointer but not recognized as one.
+    Raw,
+
+    /// A Rust reference, or a C++ lvalue reference.
+    Reference,
+
+    /// A C++ rvalue reference.
+    RvalueReference,
 }
 
 /// A structure.
@@ -91,14 +213,42 @@ pub struct Structure {
     /// The module in which the type appeared.
     pub module: Vec<String>,
 
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
     /// The size of the type, in bytes.
     pub size: u64,
 
     /// The alignment of the type, in bytes.
     pub alignment: u64,
 
+    /// Whether `alignment` was inferred from the members, rather than given by `DW_AT_alignment`.
+    pub alignment_inferred: bool,
+
     /// The members of the struct.
     pub members: Vec<StructureMember>,
+
+    /// The generic type parameters the struct was instantiated with.
+    pub template_parameters: Vec<TemplateParameter>,
+
+    /// The representation the layout of the struct is consistent with.
+    pub repr: Repr,
+}
+
+/// The representation of a structure, as chosen with `#[repr(...)]`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Repr {
+    /// `#[repr(C)]`, or any other layout C would produce for the members in declaration order.
+    C,
+
+    /// The default representation, which may reorder members.
+    Rust,
+
+    /// `#[repr(packed)]`, where members may be less aligned than their types.
+    Packed,
+
+    /// `#[repr(transparent)]`, or any layout identical to that of the only non-zero-sized member.
+    Transparent,
 }
 
 /// A structure member.
@@ -113,6 +263,331 @@ pub struct StructureMember {
     /// The offset of the member within the struct, in bytes.
     pub offset: u64,
 
-    /// The alignment of the member, in bytes.
+    /// The alignment of the member, in bytes. If not given by `DW_AT_alignment`, this is inferred
+    /// from the type.
     pub alignment: u64,
+
+    /// If the member is a bitfield, its offset within the struct, in bits. `offset` is then the
+    /// offset of the byte containing its first bit.
+    pub bit_offset: Option<u64>,
+
+    /// If the member is a bitfield, its size in bits.
+    pub bit_size: Option<u64>,
+}
+
+/// A tuple.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Tuple {
+    /// The name of the type.
+    pub name: String,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The size of the type, in bytes.
+    pub size: u64,
+
+    /// The alignment of the type, in bytes.
+    pub alignment: u64,
+
+    /// Whether `alignment` was inferred from the members, rather than given by `DW_AT_alignment`.
+    pub alignment_inferred: bool,
+
+    /// The elements of the tuple, in order. Their names are `__0`, `__1`, and so on.
+    pub elements: Vec<StructureMember>,
+}
+
+/// A structure or union whose layout is unknown.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Opaque {
+    /// The name of the type.
+    pub name: String,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+}
+
+/// A union.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Union {
+    /// The name of the type.
+    pub name: String,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The size of the type, in bytes.
+    pub size: u64,
+
+    /// The alignment of the type, in bytes.
+    pub alignment: u64,
+
+    /// Whether `alignment` was inferred from the members, rather than given by `DW_AT_alignment`.
+    pub alignment_inferred: bool,
+
+    /// The members of the union. These all overlap, so their offsets are usually zero.
+    pub members: Vec<StructureMember>,
+}
+
+/// An enum whose variants may have fields.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Enum {
+    /// The name of the type.
+    pub name: String,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The size of the type, in bytes.
+    pub size: u64,
+
+    /// The alignment of the type, in bytes.
+    pub alignment: u64,
+
+    /// Whether `alignment` was inferred from the members, rather than given by `DW_AT_alignment`.
+    pub alignment_inferred: bool,
+
+    /// The discriminant of the enum. If `None`, the enum has a single variant.
+    pub discriminant: Option<EnumDiscriminant>,
+
+    /// The variants of the enum.
+    pub variants: Vec<EnumVariant>,
+}
+
+/// The discriminant of an enum.
+///
+/// For niche-encoded enums, this overlaps the fields of the variant whose `discriminant` is `None`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EnumDiscriminant {
+    /// The index of the type.
+    pub type_index: usize,
+
+    /// The offset of the discriminant within the enum, in bytes.
+    pub offset: u64,
+}
+
+/// A variant of an enum.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EnumVariant {
+    /// The name of the variant.
+    pub name: String,
+
+    /// The value of the discriminant that selects this variant. If `None`, this variant is
+    /// selected when the discriminant doesn't match any other variant.
+    pub discriminant: Option<u64>,
+
+    /// The index of the structure holding the fields of the variant.
+    pub type_index: usize,
+
+    /// The offset of the fields of the variant within the enum, in bytes.
+    pub offset: u64,
+}
+
+/// A fixed-size array.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Array {
+    /// The name of the type, if it has one.
+    pub name: Option<String>,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The index of the element type.
+    pub type_index: usize,
+
+    /// The number of elements in each dimension, outermost first.
+    pub dimensions: Vec<u64>,
+
+    /// The distance between consecutive elements, in bytes. If `None`, this is the size of the
+    /// element type.
+    pub stride: Option<u64>,
+}
+
+/// A type alias.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Typedef {
+    /// The name of the type.
+    pub name: String,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The index of the aliased type. If `None`, the alias is for `void`.
+    pub type_index: Option<usize>,
+}
+
+/// A `const`, `volatile`, or `restrict` qualified type.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QualifiedType {
+    /// The name of the type, if it has one.
+    pub name: Option<String>,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The qualifier applied to the type.
+    pub qualifier: Qualifier,
+
+    /// The index of the qualified type. If `None`, the qualified type is `void`.
+    pub type_index: Option<usize>,
+}
+
+/// A type qualifier.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum Qualifier {
+    Const,
+    Volatile,
+    Restrict,
+}
+
+/// The type of a function, as pointed to by a function pointer.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FunctionPointer {
+    /// The name of the type, if it has one.
+    pub name: Option<String>,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The index of the return type. If `None`, the function doesn't return a value.
+    pub ret_type_index: Option<usize>,
+
+    /// The indices of the types of the arguments to the function.
+    pub arguments: Vec<usize>,
+
+    /// Whether the function takes additional arguments after `arguments`, like C's `...`.
+    pub variadic: bool,
+
+    /// The calling convention of the function.
+    pub calling_convention: CallingConvention,
+}
+
+/// The calling convention of a function.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum CallingConvention {
+    /// The platform's C calling convention.
+    C,
+
+    /// The `stdcall` calling convention.
+    StdCall,
+
+    /// Some other calling convention, as a `DW_CC_*` value.
+    Other(u8),
+}
+
+/// A pointer to a slice or string slice.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Slice {
+    /// The name of the type.
+    pub name: String,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The size of the type, in bytes.
+    pub size: u64,
+
+    /// The alignment of the type, in bytes.
+    pub alignment: u64,
+
+    /// Whether `alignment` was inferred from the members, rather than given by `DW_AT_alignment`.
+    pub alignment_inferred: bool,
+
+    /// Whether the elements may be modified through the pointer.
+    pub mutable: bool,
+
+    /// The member holding the pointer to the first element.
+    pub data_ptr: StructureMember,
+
+    /// The member holding the number of elements.
+    pub length: StructureMember,
+}
+
+/// A pointer to a trait object.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TraitObject {
+    /// The name of the type.
+    pub name: String,
+
+    /// The module in which the type appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the type appeared in.
+    pub language: Language,
+
+    /// The size of the type, in bytes.
+    pub size: u64,
+
+    /// The alignment of the type, in bytes.
+    pub alignment: u64,
+
+    /// Whether `alignment` was inferred from the members, rather than given by `DW_AT_alignment`.
+    pub alignment_inferred: bool,
+
+    /// Whether the value may be modified through the pointer.
+    pub mutable: bool,
+
+    /// The member holding the pointer to the value.
+    pub pointer: StructureMember,
+
+    /// The member holding the pointer to the vtable.
+    pub vtable: StructureMember,
+}
+
+/// A global or static variable.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Static {
+    /// The name of the variable, as written in a `static` item.
+    pub name: String,
+
+    /// The name of the variable, as it appears in the `.so`. If `None`, this is the same as `name`.
+    pub linkage_name: Option<String>,
+
+    /// The fully qualified name of the variable.
+    pub full_name: String,
+
+    /// The module in which the variable appeared.
+    pub module: Vec<String>,
+
+    /// The language of the compilation unit the variable appeared in.
+    pub language: Language,
+
+    /// The index of the type.
+    pub type_index: usize,
+
+    /// Whether the variable is in a writable section of the `.so`. This comes from where the
+    /// variable was placed, not from whether it was declared `static mut`, so a `static` with
+    /// interior mutability is mutable too. Variables whose address is unknown are assumed to be
+    /// writable.
+    pub mutable: bool,
+
+    /// The address of the variable, relative to the base of the `.so`.
+    pub address: Option<u64>,
 }
@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use dwarffi::dwarf::get_items;
+use dwarffi::dwarf::{find_alt_file, find_debug_file, find_dwp_file, get_items, DebugFiles};
 use std::{fs::read, path::PathBuf};
 
 /// Simple code streaming server with asciinema and xterm.js.
@@ -9,6 +9,31 @@ struct Args {
     #[structopt(short = "v", long = "verbose", parse(from_occurrences))]
     pub verbosity: usize,
 
+    /// A directory to look for the separate debug info of a stripped .so in, like /usr/lib/debug,
+    /// which is the default. May be given more than once.
+    #[structopt(long = "debug-dir", number_of_values = 1)]
+    pub debug_dirs: Vec<PathBuf>,
+
+    /// The separate file with the debug info of the .so, if it was stripped. By default, it's
+    /// found from the .so's build ID or .gnu_debuglink section.
+    #[structopt(long = "debug-file")]
+    pub debug_file: Option<PathBuf>,
+
+    /// The supplementary file with the debug info DWZ moved out of the .so. By default, it's found
+    /// from the .so's .gnu_debugaltlink section.
+    #[structopt(long = "alt-file")]
+    pub alt_file: Option<PathBuf>,
+
+    /// The package with the split debug info of the .so, made by dwp. By default, it's the path
+    /// of the .so or its debug file with .dwp appended, if that exists.
+    #[structopt(long = "dwp")]
+    pub dwp_file: Option<PathBuf>,
+
+    /// A directory to look for .dwo files with split debug info in, if they aren't in their
+    /// compilation directory. May be given more than once. The .so's directory is always searched.
+    #[structopt(long = "dwo-dir", number_of_values = 1)]
+    pub dwo_dirs: Vec<PathBuf>,
+
     /// The .so to generate bindings to.
     pub file: PathBuf,
 }
@@ -24,7 +49,52 @@ fn main(args: Args) -> Result<()> {
     logger.init().unwrap();
 
     let file = read(&args.file).context("Failed to read file")?;
-    let items = get_items(&file).context("Failed to get items from file")?;
+    let debug_dirs = if args.debug_dirs.is_empty() {
+        vec![PathBuf::from("/usr/lib/debug")]
+    } else {
+        args.debug_dirs
+    };
+    let debug_path = match args.debug_file {
+        Some(debug_file) => Some(debug_file),
+        None => find_debug_file(&args.file, &file, &debug_dirs)?,
+    };
+    let debug_file = match debug_path {
+        Some(ref debug_path) => Some(read(debug_path).context("Failed to read debug file")?),
+        None => None,
+    };
+
+    // DWZ is run on the debug info, so the debug file has the link to the supplementary file.
+    let alt_file = match args.alt_file {
+        Some(alt_file) => Some(alt_file),
+        None => match (&debug_path, &debug_file) {
+            (Some(debug_path), Some(debug_file)) => find_alt_file(debug_path, debug_file)?,
+            _ => find_alt_file(&args.file, &file)?,
+        },
+    };
+    let alt_file = match alt_file {
+        Some(alt_file) => Some(read(&alt_file).context("Failed to read supplementary file")?),
+        None => None,
+    };
+    let dwp_path = match args.dwp_file {
+        Some(dwp_file) => Some(dwp_file),
+        None => find_dwp_file(&args.file, debug_path.as_deref()),
+    };
+    let dwp_file = match dwp_path {
+        Some(dwp_file) => Some(read(&dwp_file).context("Failed to read .dwp file")?),
+        None => None,
+    };
+    let mut dwo_dirs = args.dwo_dirs;
+    if let Some(dir) = args.file.parent() {
+        dwo_dirs.push(dir.to_path_buf());
+    }
+
+    let debug_files = DebugFiles {
+        debug_file,
+        sup_file: alt_file,
+        dwp_file,
+        dwo_dirs,
+    };
+    let items = get_items(&file, &debug_files).context("Failed to get items from file")?;
     dwarffi::python::make_ffi(&args.file, &items)?;
     Ok(())
 }
@@ -1,7 +1,335 @@
 import ctypes
+import re
 import sys
 
+ffi_lib = ctypes.CDLL(ffi_file)
 lookup = {k: v for [k, v] in ffi_values}
+for [k, v] in ffi_values:
+    v['index'] = k
+
+ctypes_cache = {}
+int_ctypes = {
+    'UnsignedInt': {1: ctypes.c_uint8, 2: ctypes.c_uint16, 4: ctypes.c_uint32,
+                    8: ctypes.c_uint64},
+    'SignedInt': {1: ctypes.c_int8, 2: ctypes.c_int16, 4: ctypes.c_int32,
+                  8: ctypes.c_int64},
+    'Float': {4: ctypes.c_float, 8: ctypes.c_double},
+    'Bool': {1: ctypes.c_bool},
+    'Char': {1: ctypes.c_char, 4: ctypes.c_uint32},
+}
+
+
+def to_ctype(index):
+    """Returns the ctypes type for the item with the given index, or None for
+    types with no values."""
+    if isinstance(index, dict):
+        index = index['index']
+    if index in ctypes_cache:
+        return ctypes_cache[index]
+
+    item = lookup[index]
+    if item['type'] == 'BaseType':
+        if item['kind'] in ['Never', 'Unit']:
+            ty = None
+        elif item['size'] in int_ctypes[item['kind']]:
+            ty = int_ctypes[item['kind']][item['size']]
+        else:
+            ty = ctypes.c_ubyte * item['size']
+    elif item['type'] == 'PointerType':
+        pointee = to_ctype_or_void(item['type_index'])
+        if pointee is None:
+            ty = ctypes.c_void_p
+        elif lookup[item['type_index']]['type'] == 'Opaque':
+            ty = type(pointee.__name__, (ctypes.c_void_p,), {})
+        elif lookup[item['type_index']]['type'] == 'FunctionPointer':
+            # ctypes function types are already pointers.
+            ty = pointee
+        else:
+            ty = ctypes.POINTER(pointee)
+    elif item['type'] in ['Structure', 'Closure']:
+        # Structures may point to themselves, so they're cached before their
+        # fields are filled in.
+        ty = type(item['name'], (ctypes.Structure,), {'_pack_': 1})
+        ctypes_cache[index] = ty
+        ty._fields_ = struct_fields(item['members'], item['size'])
+    elif item['type'] == 'ZeroSized':
+        ty = None
+    elif item['type'] == 'Opaque':
+        # Only used to name the handle types that point to it.
+        ty = type(item['name'], (ctypes.Structure,), {'_fields_': []})
+    elif item['type'] == 'Tuple':
+        ty = type(item['name'], (TupleBase,), {
+            '_pack_': 1,
+            '_elements': [m['name'] for m in item['elements']],
+        })
+        ctypes_cache[index] = ty
+        ty._fields_ = struct_fields(item['elements'], item['size'])
+    elif item['type'] == 'Union':
+        ty = type(item['name'], (ctypes.Union,), {})
+        ctypes_cache[index] = ty
+        ty._fields_ = [(m['name'], to_ctype(m['type_index']))
+                       for m in item['members']
+                       if to_ctype(m['type_index']) is not None]
+    elif item['type'] == 'Enum':
+        ty = type(item['name'], (EnumBase,), {'_pack_': 1})
+        ctypes_cache[index] = ty
+        ty._discriminants = {v['name']: v['discriminant']
+                             for v in item['variants']}
+        ty._fields_ = enum_fields(item)
+    elif item['type'] in ['Typedef', 'QualifiedType']:
+        ty = to_ctype_or_void(item['type_index'])
+    elif item['type'] == 'FunctionPointer':
+        cc = item['calling_convention']
+        if cc == 'C':
+            functype = ctypes.CFUNCTYPE
+        elif cc == 'StdCall' and hasattr(ctypes, 'WINFUNCTYPE'):
+            functype = ctypes.WINFUNCTYPE
+        else:
+            raise TypeError('unsupported calling convention {}'.format(cc))
+        argtypes = [to_ctype(ty) for ty in item['arguments']]
+        ty = functype(to_ctype_or_void(item['ret_type_index']),
+                      *[ty for ty in argtypes if ty is not None])
+    elif item['type'] in ['Slice', 'Str']:
+        ty = type(item['name'], (FatPointer,), {
+            '_pack_': 1,
+            '_fields_': struct_fields([item['data_ptr'], item['length']],
+                                      item['size']),
+            '_is_str': item['type'] == 'Str',
+        })
+        ty._element = to_ctype_or_void(
+            lookup[item['data_ptr']['type_index']]['type_index'])
+    elif item['type'] == 'TraitObject':
+        ty = type(item['name'], (ctypes.Structure,), {
+            '_pack_': 1,
+            '_fields_': struct_fields([item['pointer'], item['vtable']],
+                                      item['size']),
+        })
+    elif item['type'] == 'Array':
+        ty = to_ctype(item['type_index'])
+        if item['stride'] is not None and item['stride'] != ctypes.sizeof(ty):
+            raise TypeError('arrays with padding between elements are not '
+                            'supported')
+        for n in reversed(item['dimensions']):
+            ty = ty * n
+    else:
+        raise TypeError('no ctypes equivalent for {} {}'.format(
+            item['type'], repr(item['name'])))
+
+    ctypes_cache[index] = ty
+    return ty
+
+
+class FatPointer(ctypes.Structure):
+    """The base class for the ctypes types of slices and string slices, which
+    converts them to and from Python values."""
+
+    @property
+    def value(self):
+        addr = ctypes.cast(self.data_ptr, ctypes.c_void_p).value
+        if self._is_str:
+            return ctypes.string_at(addr, self.length).decode('utf-8')
+        elif self._element in [ctypes.c_uint8, ctypes.c_char]:
+            return ctypes.string_at(addr, self.length)
+        return list(ctypes.cast(addr, ctypes.POINTER(self._element))
+                    [:self.length])
+
+    @classmethod
+    def from_value(cls, value):
+        if isinstance(value, str):
+            value = value.encode('utf-8')
+        buf = (cls._element * len(value))(*value)
+        ptr_type = dict(cls._fields_)['data_ptr']
+        fat = cls(data_ptr=ctypes.cast(buf, ptr_type), length=len(value))
+        # Keep the buffer alive as long as the pointer to it.
+        fat._buf = buf
+        return fat
+
+    @classmethod
+    def from_param(cls, value):
+        if isinstance(value, cls):
+            return value
+        return cls.from_value(value)
+
+
+class TupleBase(ctypes.Structure):
+    """The base class for the ctypes types of tuples, which converts them to
+    and from Python tuples. Zero-sized elements are None."""
+
+    @property
+    def value(self):
+        return tuple(getattr(self, name, None) for name in self._elements)
+
+    @classmethod
+    def from_value(cls, value):
+        fields = dict(cls._fields_)
+        return cls(**{name: v for (name, v) in zip(cls._elements, value)
+                      if name in fields})
+
+    @classmethod
+    def from_param(cls, value):
+        if isinstance(value, cls):
+            return value
+        return cls.from_value(value)
+
+
+class EnumBase(ctypes.Structure):
+    """The base class for the ctypes types of enums, which hold a union of the
+    discriminant and the fields of each variant."""
+
+    @property
+    def variant(self):
+        """The name of the variant the enum holds."""
+        if '_discriminant' not in dict(type(self._variants)._fields_):
+            tag = None
+        else:
+            field = self._variants._discriminant
+            # Discriminants are compared modulo their size, since negative
+            # ones are recorded as unsigned.
+            modulus = 1 << (8 * ctypes.sizeof(dict(field._fields_)['value']))
+            tag = field.value % modulus
+        default = None
+        for name, discriminant in self._discriminants.items():
+            if discriminant is None:
+                default = name
+            elif tag is not None and discriminant % modulus == tag:
+                return name
+        return default
+
+    @property
+    def value(self):
+        """The fields of the variant the enum holds."""
+        return getattr(self._variants, self.variant).value
+
+    @classmethod
+    def new(cls, variant, value=None):
+        """Returns an enum holding the given variant, with the given fields."""
+        enum = cls()
+        if value is not None:
+            getattr(enum._variants, variant).value = value
+        discriminant = cls._discriminants[variant]
+        if discriminant is not None:
+            enum._variants._discriminant.value = discriminant
+        return enum
+
+
+def enum_fields(item):
+    """Returns the _fields_ for an Enum item: a union of the discriminant and
+    the fields of each variant at their offsets, padded to the enum's size."""
+    def at_offset(name, offset, ty):
+        fields = [('value', ty)]
+        if offset > 0:
+            fields.insert(0, ('_pad', ctypes.c_ubyte * offset))
+        return type(name, (ctypes.Structure,),
+                    {'_pack_': 1, '_fields_': fields})
+
+    members = []
+    discriminant = item['discriminant']
+    if discriminant is not None:
+        ty = to_ctype(discriminant['type_index'])
+        members.append(('_discriminant', at_offset(
+            '_discriminant', discriminant['offset'], ty)))
+    for v in item['variants']:
+        ty = to_ctype(v['type_index'])
+        if ty is not None:
+            members.append((v['name'], at_offset(v['name'], v['offset'], ty)))
+    variants = type(item['name'] + '_variants', (ctypes.Union,),
+                    {'_pack_': 1, '_fields_': members})
+
+    fields = [('_variants', variants)]
+    if ctypes.sizeof(variants) < item['size']:
+        fields.append(('_pad', ctypes.c_ubyte *
+                       (item['size'] - ctypes.sizeof(variants))))
+    return fields
+
+
+def to_ctype_or_void(index):
+    """Like to_ctype, but also accepts None for void."""
+    return None if index is None else to_ctype(index)
+
+
+def to_cfunc(item):
+    """Returns the ctypes function for the given Function item."""
+    func = getattr(ffi_lib, item['linkage_name'])
+    if item['unknown_signature']:
+        # Only the symbol is known, so restype and argtypes are left to the
+        # caller to set.
+        return func
+    func.restype = to_ctype_or_void(item['ret_type_index'])
+    # Like the Rust ABI, zero-sized arguments aren't passed at all.
+    argtypes = [to_ctype(ty) for [_, ty] in item['arguments']]
+    func.argtypes = [ty for ty in argtypes if ty is not None]
+    return func
+
+
+def to_cvar(item):
+    """Returns the ctypes value for the given Static item, which reads and
+    writes the variable in the library."""
+    symbol = item['linkage_name'] or item['name']
+    return to_ctype(item['type_index']).in_dll(ffi_lib, symbol)
+
+
+def struct_fields(members, size):
+    """Returns the _fields_ for a packed ctypes.Structure with the given
+    members, which Rust may have reordered."""
+    fields = []
+    offset = 0
+    # The bitfield storage unit being filled, as [offset, ctype, bits used].
+    unit = None
+
+    def pad(to):
+        if to < offset:
+            raise TypeError('overlapping members are not supported')
+        elif to > offset:
+            fields.append(('_pad{}'.format(offset),
+                           ctypes.c_ubyte * (to - offset)))
+
+    for m in sorted(members, key=member_bit_offset):
+        ty = to_ctype(m['type_index'])
+        if ty is None:
+            continue
+        if m.get('bit_size') is None:
+            pad(m['offset'])
+            fields.append((m['name'], ty))
+            offset = m['offset'] + ctypes.sizeof(ty)
+            unit = None
+            continue
+
+        unit_offset = m['bit_offset'] // (8 * ctypes.sizeof(ty)) * \
+            ctypes.sizeof(ty)
+        if unit is None or unit[0] != unit_offset or unit[1] is not ty:
+            pad(unit_offset)
+            unit = [unit_offset, ty, 0]
+            offset = unit_offset + ctypes.sizeof(ty)
+        used = m['bit_offset'] - 8 * unit_offset
+        if used > unit[2]:
+            fields.append(('_pad_bits{}'.format(m['bit_offset']), ty,
+                           used - unit[2]))
+        fields.append((m['name'], ty, m['bit_size']))
+        unit[2] = used + m['bit_size']
+    pad(size)
+    return fields
+
+
+def member_bit_offset(m):
+    """Returns the offset of a member in bits, for sorting."""
+    if m.get('bit_offset') is not None:
+        return m['bit_offset']
+    return 8 * m['offset']
+
+
+def friendly_name(item):
+    """Returns the name of an item as a Python identifier, with generic
+    parameters spelled out, like Vec_u8 for Vec<u8>."""
+    name = item['name']
+    if name is None:
+        return 'void'
+    params = item.get('template_parameters') or []
+    if params:
+        name = '_'.join([name.split('<')[0]] + [
+            'void' if p['type_index'] is None
+            else friendly_name(lookup[p['type_index']])
+            for p in params])
+    return re.sub(r'\W+', '_', name).strip('_')
 
 
 def Module():
@@ -33,12 +361,16 @@ def Module():
 values = Module()
 
 for [_, value] in ffi_values:
+    name = value['name']
+    if name is None or value['type'] == 'Closure':
+        continue
+    if value.get('template_parameters'):
+        name = friendly_name(value)
     cell = [values]
     for m in value['module']:
         if m not in cell[0]:
             cell[0][m] = Module()
         cell = [cell[0][m]]
-    name = value['name']
     if name in cell[0]:
         print(cell[0][name])
         print(value)
@@ -50,6 +382,12 @@ class Root:
     def __getattr__(self, name):
         if name == '_ffi_values':
             return ffi_values
+        elif name == '_to_ctype':
+            return to_ctype
+        elif name == '_to_cfunc':
+            return to_cfunc
+        elif name == '_to_cvar':
+            return to_cvar
         elif name in values:
             return values[name]
         raise AttributeError(
@@ -0,0 +1,125 @@
+use dwarffi::{
+    dwarf::{get_items, DebugFiles},
+    item::{Function, Item, Qualifier, Static, Structure},
+};
+use std::{fs, path::Path};
+
+fn fixture_items(name: &str) -> Vec<(usize, Item)> {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let file = fs::read(fixtures.join(name)).unwrap();
+    // The .dwo is found by name, since its compilation directory is where the fixture was built.
+    let debug_files = DebugFiles {
+        dwo_dirs: vec![fixtures],
+        ..Default::default()
+    };
+    get_items(&file, &debug_files).unwrap()
+}
+
+fn function<'a>(items: &'a [(usize, Item)], name: &str) -> &'a Function {
+    items
+        .iter()
+        .find_map(|(_, item)| match item {
+            Item::Function(f) if f.full_name == name => Some(f),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("missing {}", name))
+}
+
+fn structure<'a>(items: &'a [(usize, Item)], name: &str) -> &'a Structure {
+    items
+        .iter()
+        .find_map(|(_, item)| match item {
+            Item::Structure(s) if s.name == name => Some(s),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("missing {}", name))
+}
+
+fn static_<'a>(items: &'a [(usize, Item)], name: &str) -> &'a Static {
+    items
+        .iter()
+        .find_map(|(_, item)| match item {
+            Item::Static(s) if s.name == name => Some(s),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("missing {}", name))
+}
+
+/// Spells out a type, to compare it without depending on item indices.
+fn type_name(items: &[(usize, Item)], index: usize) -> String {
+    let item = items
+        .iter()
+        .find(|(i, _)| *i == index)
+        .map(|(_, item)| item)
+        .unwrap_or_else(|| panic!("missing type {}", index));
+    let pointee = |index: Option<usize>| match index {
+        Some(index) => type_name(items, index),
+        None => "void".to_string(),
+    };
+    match item {
+        Item::BaseType(ty) => ty.name.clone(),
+        Item::Structure(ty) => format!("struct {}", ty.name),
+        Item::PointerType(ty) => format!("{} *", pointee(ty.type_index)),
+        Item::QualifiedType(ty) if matches!(ty.qualifier, Qualifier::Const) => {
+            format!("const {}", pointee(ty.type_index))
+        }
+        item => panic!("unexpected type {:?}", item),
+    }
+}
+
+fn check_items(items: &[(usize, Item)]) {
+    let pair = structure(items, "pair");
+    let members = pair
+        .members
+        .iter()
+        .map(|m| (m.name.as_str(), type_name(items, m.type_index), m.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        members,
+        [
+            ("first", "int".to_string(), 0),
+            ("second", "int".to_string(), 4)
+        ]
+    );
+
+    let sum = function(items, "sum");
+    assert_eq!(type_name(items, sum.ret_type_index.unwrap()), "int");
+    let arguments = sum
+        .arguments
+        .iter()
+        .map(|(name, ty)| (name.as_deref(), type_name(items, *ty)))
+        .collect::<Vec<_>>();
+    assert_eq!(arguments, [(Some("p"), "struct pair".to_string())]);
+
+    let scale = function(items, "scale");
+    assert_eq!(type_name(items, scale.ret_type_index.unwrap()), "long int");
+    let arguments = scale
+        .arguments
+        .iter()
+        .map(|(name, ty)| (name.as_deref(), type_name(items, *ty)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        arguments,
+        [
+            (Some("p"), "const struct pair *".to_string()),
+            (Some("factor"), "long int".to_string())
+        ]
+    );
+}
+
+/// Strings are DW_FORM_line_strp and DW_FORM_strp, and the unit's ranges are in .debug_rnglists.
+#[test]
+fn dwarf5() {
+    let items = fixture_items("libdwarf5-unsplit.so");
+    check_items(&items);
+    assert!(static_(&items, "counter").address.is_some());
+}
+
+/// Strings are DW_FORM_strx into the .dwo's .debug_str_offsets, and the address of `counter` is a
+/// DW_OP_addrx into the .so's .debug_addr.
+#[test]
+fn split_dwarf5() {
+    let items = fixture_items("libdwarf5.so");
+    check_items(&items);
+    assert_eq!(static_(&items, "counter").address, Some(0x4008));
+}
@@ -0,0 +1,18 @@
+/* Built with `gcc -shared -fPIC -g -gdwarf-5 -gsplit-dwarf -o libdwarf5.so dwarf5.c`, so the
+ * location of `counter` is a DW_OP_addrx into .debug_addr, and with
+ * `gcc -shared -fPIC -g -gdwarf-5 -O2 -ffunction-sections -o libdwarf5-unsplit.so dwarf5.c`, so
+ * the unit's ranges are in .debug_rnglists and its strings are DW_FORM_line_strp. */
+struct pair {
+    int first;
+    int second;
+};
+
+int counter = 3;
+
+int sum(struct pair p) {
+    return p.first + p.second + counter;
+}
+
+long scale(const struct pair *p, long factor) {
+    return (p->first + p->second) * factor;
+}